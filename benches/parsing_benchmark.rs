@@ -76,6 +76,20 @@ macro_rules! bench_gfa_withopt {
     };
 }
 
+macro_rules! bench_gfa_names {
+    ($name:ident, $gfa:literal) => {
+        bench_gfa!(parse_lines_noopt, "names_excluding_optionals", $name, $gfa);
+    };
+    ($name:ident, $gfa:literal, withopt) => {
+        bench_gfa!(
+            parse_lines_withopt,
+            "names_including_optionals",
+            $name,
+            $gfa
+        );
+    };
+}
+
 // bench_gfa_noopt!(cov_noopt, "relabeledSeqs.gfa");
 bench_gfa_noopt!(a3105_noopt, "A-3105.gfa");
 bench_gfa_noopt!(a3105_sort_noopt, "A-3105.sort.gfa");
@@ -88,6 +102,30 @@ bench_gfa_withopt!(a3105_sort_withopt, "A-3105.sort.gfa");
 bench_gfa_withopt!(drb1_withopt, "DRB1-3123.gfa");
 bench_gfa_withopt!(drb1_sort_withopt, "DRB1-3123.sort.gfa");
 
+bench_gfa_names!(a3105_names_noopt, "A-3105.gfa");
+bench_gfa_names!(drb1_names_noopt, "DRB1-3123.gfa");
+bench_gfa_names!(a3105_names_withopt, "A-3105.gfa", withopt);
+bench_gfa_names!(drb1_names_withopt, "DRB1-3123.gfa", withopt);
+
+// One optional field of each type, to exercise each of the field scanners
+fn optional_fields(c: &mut Criterion) {
+    let fields: Vec<&[u8]> = vec![
+        b"RC:i:12345",
+        b"NM:i:-3",
+        b"dc:f:-1.25e-3",
+        b"SN:Z:chr1 with spaces",
+        b"JS:J:{\"a\":[1,2]}",
+        b"CH:A:x",
+        b"HX:H:1A2B3C",
+        b"BI:B:I1,2,3,4",
+    ];
+    c.bench_with_input(
+        BenchmarkId::new("optional_fields", fields.len()),
+        &fields,
+        |b, f| b.iter(|| <OptionalFields as OptFields>::parse(f.iter())),
+    );
+}
+
 criterion_group!(
     name = no_opt_benches;
     config = Criterion::default().sample_size(25);
@@ -100,4 +138,10 @@ criterion_group!(
     targets = /*cov_withopt,*/ a3105_withopt, a3105_sort_withopt, drb1_withopt, drb1_sort_withopt
 );
 
-criterion_main!(no_opt_benches, with_opt_benches);
+criterion_group!(
+    name = field_benches;
+    config = Criterion::default().sample_size(25);
+    targets = a3105_names_noopt, drb1_names_noopt, a3105_names_withopt, drb1_names_withopt, optional_fields
+);
+
+criterion_main!(no_opt_benches, with_opt_benches, field_benches);
@@ -0,0 +1,291 @@
+//! Derive macro for the `OptFields` trait of the `gfa` crate, which
+//! is re-exported as `gfa::optfields::OptFields` when the `derive`
+//! feature of `gfa` is enabled.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Fields, GenericArgument,
+    Ident, LitByteStr, LitStr, PathArguments, Type,
+};
+
+/// Implement `OptFields` for a struct with named fields, each of
+/// which is either:
+///
+/// * A field for a single tag, marked with `#[tag("LN")]`, with the
+///   type `Option<V>`, where `V` implements `OptFieldValue`. Fields
+///   with the tag, but a value of another type, fail to parse.
+/// * The catch-all field, marked with `#[other_tags]`, which holds
+///   the optional fields with all other tags, so that they're kept
+///   when the struct is written. Its type must implement
+///   `OptFields`, e.g. `Vec<OptField>`. Without a catch-all field,
+///   the other optional fields are skipped.
+///
+/// As `OptFields` requires `Default` and `Clone`, those must be
+/// implemented as well.
+#[proc_macro_derive(OptFields, attributes(tag, other_tags))]
+pub fn derive_opt_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// A field of the struct that holds the value of one tag.
+struct TaggedField {
+    ident: Ident,
+    tag: LitByteStr,
+    value_type: Type,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(not_supported(&input)),
+        },
+        _ => return Err(not_supported(&input)),
+    };
+
+    let mut tagged: Vec<TaggedField> = Vec::new();
+    let mut other: Option<Ident> = None;
+
+    for field in fields {
+        let ident = field.ident.clone().unwrap();
+        let mut tag = None;
+        let mut is_other = false;
+        for attr in &field.attrs {
+            if attr.path().is_ident("tag") {
+                tag = Some(parse_tag(&attr.parse_args()?)?);
+            } else if attr.path().is_ident("other_tags") {
+                attr.meta.require_path_only()?;
+                is_other = true;
+            }
+        }
+
+        match (tag, is_other) {
+            (Some(tag), false) => {
+                if tagged.iter().any(|f| f.tag.value() == tag.value()) {
+                    return Err(Error::new_spanned(tag, "duplicate tag"));
+                }
+                let value_type =
+                    option_contents(&field.ty).ok_or_else(|| {
+                        Error::new_spanned(
+                            &field.ty,
+                            "fields with a tag must have an `Option` type",
+                        )
+                    })?;
+                tagged.push(TaggedField {
+                    ident,
+                    tag,
+                    value_type: value_type.clone(),
+                });
+            }
+            (None, true) if other.is_none() => other = Some(ident),
+            (None, true) => {
+                return Err(Error::new_spanned(
+                    field,
+                    "only one field can hold the other tags",
+                ))
+            }
+            _ => {
+                return Err(Error::new_spanned(
+                    field,
+                    "expected either `#[tag(\"..\")]` or `#[other_tags]`",
+                ))
+            }
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    let idents: Vec<_> = tagged.iter().map(|f| &f.ident).collect();
+    let tags: Vec<_> = tagged.iter().map(|f| &f.tag).collect();
+    let value_types: Vec<_> = tagged.iter().map(|f| &f.value_type).collect();
+
+    let get_other = match &other {
+        Some(other) => quote! {
+            ::gfa::optfields::OptFields::get_field(&self.#other, tag)
+        },
+        None => quote! { ::std::option::Option::None },
+    };
+    let fields_other = other.as_ref().map(|other| {
+        quote! {
+            let other = ::gfa::optfields::OptFields::fields(&self.#other);
+            fields.extend(other.iter().cloned());
+        }
+    });
+    let merge_other = other.as_ref().map(|other| {
+        quote! {
+            ::gfa::optfields::OptFields::merge(
+                &mut self.#other,
+                ::std::mem::take(&mut other.#other),
+            );
+        }
+    });
+    let merge_arg = if tagged.is_empty() && other.is_none() {
+        quote! { _other }
+    } else {
+        quote! { mut other }
+    };
+    let (new_other, keep_other, parse_other) = match &other {
+        Some(other) => (
+            quote! { let mut other = ::std::vec::Vec::new(); },
+            quote! { other.push(field) },
+            quote! {
+                result.#other = ::gfa::optfields::OptFields::parse_with(
+                    other,
+                    &mut on_error,
+                )?;
+            },
+        ),
+        None => (quote! {}, quote! { () }, quote! {}),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::gfa::optfields::OptFields for #name #ty_generics
+        #where_clause
+        {
+            fn get_field(
+                &self,
+                tag: &[u8],
+            ) -> ::std::option::Option<
+                ::std::borrow::Cow<'_, ::gfa::optfields::OptField>,
+            > {
+                match tag {
+                    #(#tags => self.#idents.as_ref().map(|value| {
+                        let value = ::std::clone::Clone::clone(value);
+                        ::std::borrow::Cow::Owned(::gfa::optfields::OptField {
+                            tag: *#tags,
+                            value: ::gfa::optfields::OptFieldValue::into_value(
+                                value,
+                            ),
+                        })
+                    }),)*
+                    _ => #get_other,
+                }
+            }
+
+            fn fields(
+                &self,
+            ) -> ::std::borrow::Cow<'_, [::gfa::optfields::OptField]> {
+                let mut fields = ::std::vec::Vec::new();
+                #(if let ::std::option::Option::Some(value) = &self.#idents {
+                    let value = ::std::clone::Clone::clone(value);
+                    fields.push(::gfa::optfields::OptField {
+                        tag: *#tags,
+                        value: ::gfa::optfields::OptFieldValue::into_value(
+                            value,
+                        ),
+                    });
+                })*
+                #fields_other
+                ::std::borrow::Cow::Owned(fields)
+            }
+
+            fn parse_with<__I, __E>(
+                input: __I,
+                mut on_error: __E,
+            ) -> ::gfa::parser::GFAFieldResult<Self>
+            where
+                __I: ::std::iter::IntoIterator,
+                __I::Item: ::std::convert::AsRef<[u8]>,
+                __E: ::std::ops::FnMut(
+                    ::gfa::parser::ParseFieldError,
+                ) -> ::gfa::parser::GFAFieldResult<()>,
+            {
+                use ::gfa::optfields::{OptField, OptFieldValue};
+                use ::gfa::parser::{OptFieldError, ParseFieldError};
+
+                let mut result = <Self as ::std::default::Default>::default();
+                #new_other
+                for field in input {
+                    let bytes = ::std::convert::AsRef::<[u8]>::as_ref(&field);
+                    match bytes.get(..2) {
+                        #(::std::option::Option::Some(#tags) => {
+                            match OptField::parse(bytes) {
+                                Ok(parsed) => {
+                                    let type_byte = parsed.value.type_byte();
+                                    match <#value_types as OptFieldValue>::from_value(
+                                        parsed.value,
+                                    ) {
+                                        Some(value) => {
+                                            result.#idents = Some(value)
+                                        }
+                                        None => on_error(
+                                            ParseFieldError::InvalidOptField(
+                                                ::std::boxed::Box::from(&#tags[..]),
+                                                OptFieldError::UnexpectedType(
+                                                    type_byte,
+                                                ),
+                                            ),
+                                        )?,
+                                    }
+                                }
+                                Err(err) => on_error(err)?,
+                            }
+                        })*
+                        _ => #keep_other,
+                    }
+                }
+                #parse_other
+                Ok(result)
+            }
+
+            fn merge(&mut self, #merge_arg: Self) {
+                #(if let ::std::option::Option::Some(value) =
+                    other.#idents.take()
+                {
+                    self.#idents = ::std::option::Option::Some(value);
+                })*
+                #merge_other
+            }
+        }
+    })
+}
+
+fn not_supported(input: &DeriveInput) -> Error {
+    Error::new_spanned(
+        &input.ident,
+        "OptFields can only be derived for structs with named fields",
+    )
+}
+
+/// Parse the tag of a `#[tag("..")]` attribute, which must match
+/// [A-Za-z][A-Za-z0-9].
+fn parse_tag(lit: &LitStr) -> syn::Result<LitByteStr> {
+    let tag = lit.value();
+    match tag.as_bytes() {
+        [a, b] if a.is_ascii_alphabetic() && b.is_ascii_alphanumeric() => {
+            Ok(LitByteStr::new(tag.as_bytes(), lit.span()))
+        }
+        _ => Err(Error::new_spanned(
+            lit,
+            "tags must be two characters matching [A-Za-z][A-Za-z0-9]",
+        )),
+    }
+}
+
+/// The type `T` of a field with the type `Option<T>`.
+fn option_contents(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(path) => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+            match &args.args[0] {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
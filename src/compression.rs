@@ -0,0 +1,252 @@
+//! Support for reading gzip and BGZF compressed GFA files.
+//!
+//! Plain gzip files can only be read sequentially, while BGZF files,
+//! as produced by `bgzip`, consist of independently compressed blocks
+//! and support random access through virtual offsets. A virtual
+//! offset packs the offset of a block in the compressed file into the
+//! upper 48 bits, and the offset within the decompressed block into
+//! the lower 16 bits, as in htslib.
+
+use flate2::bufread::{DeflateDecoder, MultiGzDecoder};
+
+use std::{
+    fs::File,
+    io::{self, prelude::*, BufReader, SeekFrom},
+    path::Path,
+};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// The fixed part of a gzip member header, up to and including XLEN
+const GZIP_HEADER_LEN: usize = 12;
+// The CRC32 and ISIZE fields after the compressed data
+const GZIP_FOOTER_LEN: usize = 8;
+
+/// Returns true if the bytes start with the gzip magic bytes. BGZF
+/// files are also gzip files.
+#[inline]
+pub fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&GZIP_MAGIC)
+}
+
+/// Returns true if the bytes start with a BGZF block header, i.e. a
+/// gzip header with the `BC` extra subfield.
+#[inline]
+pub fn is_bgzf(bytes: &[u8]) -> bool {
+    is_gzip(bytes)
+        && bytes.len() >= 16
+        && bytes[3] & 4 != 0
+        && bytes[12..14] == *b"BC"
+}
+
+/// Open a file for reading, transparently decompressing it if it's
+/// gzip or BGZF compressed.
+pub fn open_file<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if is_gzip(reader.fill_buf()?) {
+        let decoder = MultiGzDecoder::new(reader);
+        Ok(Box::new(BufReader::new(decoder)))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Reader for BGZF compressed data that keeps track of the virtual
+/// offset, and can seek to any virtual offset it has produced.
+#[derive(Debug)]
+pub struct BgzfReader<R> {
+    inner: R,
+    block_offset: u64,
+    next_block_offset: u64,
+    block: Vec<u8>,
+    pos: usize,
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl<R: Read + Seek> BgzfReader<R> {
+    /// Create a reader for BGZF data, which must start at the
+    /// beginning of `inner`, as offsets are relative to it.
+    pub fn new(inner: R) -> Self {
+        BgzfReader {
+            inner,
+            block_offset: 0,
+            next_block_offset: 0,
+            block: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// The virtual offset of the next byte that will be read.
+    #[inline]
+    pub fn virtual_offset(&self) -> u64 {
+        (self.block_offset << 16) | self.pos as u64
+    }
+
+    /// Seek to a virtual offset, as returned by `virtual_offset`.
+    pub fn seek_virtual(&mut self, offset: u64) -> io::Result<()> {
+        let block_offset = offset >> 16;
+        let pos = (offset & 0xFFFF) as usize;
+
+        if block_offset != self.block_offset || self.block.is_empty() {
+            self.inner.seek(SeekFrom::Start(block_offset))?;
+            self.next_block_offset = block_offset;
+            self.read_block()?;
+        }
+
+        if pos > self.block.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "virtual offset is past the end of its BGZF block",
+            ));
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    /// Read and decompress the next block, returning false at the end
+    /// of the input.
+    fn read_block(&mut self) -> io::Result<bool> {
+        self.block.clear();
+        self.pos = 0;
+
+        let mut header = [0u8; GZIP_HEADER_LEN];
+        match self.inner.read_exact(&mut header) {
+            Ok(()) => (),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(false)
+            }
+            Err(err) => return Err(err),
+        }
+        if !is_gzip(&header) || header[3] & 4 == 0 {
+            return Err(invalid_data("missing BGZF block header"));
+        }
+
+        let xlen = u16::from_le_bytes([header[10], header[11]]) as usize;
+        let mut extra = vec![0u8; xlen];
+        self.inner.read_exact(&mut extra)?;
+        let block_size = bgzf_block_size(&extra)
+            .ok_or_else(|| invalid_data("missing BGZF block size"))?;
+
+        let data_len = block_size
+            .checked_sub(GZIP_HEADER_LEN + xlen)
+            .filter(|&len| len >= GZIP_FOOTER_LEN)
+            .ok_or_else(|| invalid_data("invalid BGZF block size"))?;
+        let mut data = vec![0u8; data_len];
+        self.inner.read_exact(&mut data)?;
+
+        let (cdata, footer) = data.split_at(data_len - GZIP_FOOTER_LEN);
+        let isize =
+            u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]])
+                as usize;
+        self.block.reserve(isize);
+        DeflateDecoder::new(cdata).read_to_end(&mut self.block)?;
+        if self.block.len() != isize {
+            return Err(invalid_data("BGZF block has the wrong size"));
+        }
+
+        self.block_offset = self.next_block_offset;
+        self.next_block_offset += block_size as u64;
+        Ok(true)
+    }
+}
+
+/// Finds the total block size in the `BC` subfield of the gzip extra
+/// field of a BGZF block.
+fn bgzf_block_size(mut extra: &[u8]) -> Option<usize> {
+    while extra.len() >= 4 {
+        let len = u16::from_le_bytes([extra[2], extra[3]]) as usize;
+        let field = extra.get(4..4 + len)?;
+        if extra[..2] == *b"BC" && len == 2 {
+            return Some(u16::from_le_bytes([field[0], field[1]]) as usize + 1);
+        }
+        extra = &extra[4 + len..];
+    }
+    None
+}
+
+impl<R: Read + Seek> BufRead for BgzfReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // Loop, as the last block of a BGZF file is empty
+        while self.pos == self.block.len() {
+            if !self.read_block()? {
+                break;
+            }
+        }
+        Ok(&self.block[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.block.len());
+    }
+}
+
+impl<R: Read + Seek> Read for BgzfReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_compressed_files() {
+        let read_all = |path: &str| {
+            let mut buf = Vec::new();
+            open_file(path).unwrap().read_to_end(&mut buf).unwrap();
+            buf
+        };
+
+        let plain = read_all("./test/gfas/lil.gfa");
+        assert_eq!(plain, read_all("./test/gfas/lil.gfa.gz"));
+        assert_eq!(plain, read_all("./test/gfas/lil.bgzf.gfa.gz"));
+    }
+
+    #[test]
+    fn bgzf_virtual_offsets() {
+        let bytes = std::fs::read("./test/gfas/lil.bgzf.gfa.gz").unwrap();
+        assert!(is_bgzf(&bytes));
+        assert!(!is_bgzf(&std::fs::read("./test/gfas/lil.gfa.gz").unwrap()));
+
+        let mut reader = BgzfReader::new(io::Cursor::new(bytes));
+        let mut lines = Vec::new();
+        loop {
+            let offset = reader.virtual_offset();
+            let mut line = Vec::new();
+            if reader.read_until(b'\n', &mut line).unwrap() == 0 {
+                break;
+            }
+            lines.push((offset, line));
+        }
+
+        let plain = std::fs::read("./test/gfas/lil.gfa").unwrap();
+        let contents: Vec<u8> =
+            lines.iter().flat_map(|(_, line)| line.clone()).collect();
+        assert_eq!(contents, plain);
+        // The test file has several blocks
+        assert!(lines.iter().any(|(offset, _)| offset >> 16 != 0));
+
+        for (offset, line) in lines.iter().rev() {
+            reader.seek_virtual(*offset).unwrap();
+            let mut buf = Vec::new();
+            reader.read_until(b'\n', &mut buf).unwrap();
+            assert_eq!(&buf, line);
+        }
+    }
+}
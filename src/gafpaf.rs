@@ -48,7 +48,7 @@ impl<T: OptFields> Display for GAF<T> {
             self.quality
         )?;
 
-        for opt in self.optional.fields() {
+        for opt in self.optional.fields().iter() {
             write!(f, "\t{}", opt)?;
         }
 
@@ -210,7 +210,7 @@ impl<T: OptFields> Display for PAF<T> {
             self.quality
         )?;
 
-        for opt in self.optional.fields() {
+        for opt in self.optional.fields().iter() {
             write!(f, "\t{}", opt)?;
         }
 
@@ -262,7 +262,8 @@ where
     let block_length = parse_next(&mut input)?;
     let quality = parse_next(&mut input)?;
 
-    let optional = T::parse(input);
+    // Optional fields that fail to parse are skipped
+    let optional = T::parse_with(input, |_| Ok(())).ok()?;
 
     Some(PAF {
         query_seq_name,
@@ -316,6 +317,7 @@ mod tests {
         use GAFStep::*;
         use Orientation::*;
 
+        #[allow(clippy::upper_case_acronyms)]
         type GAF = super::GAF<OptionalFields>;
         let gaf_in1 =
             b"read1\t6\t0\t6\t+\t>s2>s3>s4\t12\t2\t8\t6\t6\t60\tcg:Z:6M";
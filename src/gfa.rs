@@ -1,9 +1,18 @@
+//! This module defines the various GFA line types, the GFA object,
+//! and some utility functions and types.
+
+pub mod borrowed;
 pub mod name_conversion;
 pub mod orientation;
+pub mod path_walk;
+pub mod position;
 pub mod traits;
+pub mod version;
 
 pub use self::orientation::*;
+pub use self::position::*;
 pub use self::traits::*;
+pub use self::version::*;
 
 use crate::{cigar::CIGAR, optfields::*};
 
@@ -11,18 +20,63 @@ use bstr::{BStr, ByteSlice};
 #[cfg(feature = "serde1")]
 use serde::{Deserialize, Serialize};
 
-/// This module defines the various GFA line types, the GFA object,
-/// and some utility functions and types.
-
 /// Simple representation of a parsed GFA file, using a Vec<T> to
-/// store each separate GFA line type.
-#[derive(Default, Debug, Clone, PartialEq, PartialOrd)]
+/// store each separate GFA line type. GFA2 segments are stored
+/// separately from GFA1 segments, as they include a length field.
+///
+/// Comments and lines with unknown record types are only kept if the
+/// parser was configured to preserve them, in which case the order
+/// of the lines in the file is also recorded in `line_order`.
+///
+/// The header of a new GFA is empty, rather than `Header::default()`,
+/// and header lines are merged into it as they're inserted, so the
+/// version is only set if a header line includes it.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct GFA<N, T: OptFields> {
     pub header: Header<T>,
     pub segments: Vec<Segment<N, T>>,
     pub links: Vec<Link<N, T>>,
+    pub jumps: Vec<Jump<N, T>>,
     pub containments: Vec<Containment<N, T>>,
     pub paths: Vec<Path<N, T>>,
+    pub walks: Vec<Walk<N, T>>,
+    pub gfa2_segments: Vec<GFA2Segment<N, T>>,
+    pub edges: Vec<Edge<N, T>>,
+    pub fragments: Vec<Fragment<N, T>>,
+    pub gaps: Vec<Gap<N, T>>,
+    pub ordered_groups: Vec<OrderedGroup<N, T>>,
+    pub unordered_groups: Vec<UnorderedGroup<N, T>>,
+    /// Comments, empty lines, and lines with unknown record types,
+    /// stored as they were in the file, without the line terminator.
+    pub raw_lines: Vec<Vec<u8>>,
+    /// The kind and index of each line, in the order they were
+    /// inserted, if the GFA keeps track of the line order.
+    pub line_order: Option<Vec<(LineKind, usize)>>,
+}
+
+impl<N, T: OptFields> Default for GFA<N, T> {
+    fn default() -> Self {
+        GFA {
+            header: Header {
+                version: None,
+                optional: Default::default(),
+            },
+            segments: Default::default(),
+            links: Default::default(),
+            jumps: Default::default(),
+            containments: Default::default(),
+            paths: Default::default(),
+            walks: Default::default(),
+            gfa2_segments: Default::default(),
+            edges: Default::default(),
+            fragments: Default::default(),
+            gaps: Default::default(),
+            ordered_groups: Default::default(),
+            unordered_groups: Default::default(),
+            raw_lines: Default::default(),
+            line_order: None,
+        }
+    }
 }
 
 /// Enum containing the different kinds of GFA lines.
@@ -31,8 +85,101 @@ pub enum Line<N, T: OptFields> {
     Header(Header<T>),
     Segment(Segment<N, T>),
     Link(Link<N, T>),
+    Jump(Jump<N, T>),
     Containment(Containment<N, T>),
     Path(Path<N, T>),
+    Walk(Walk<N, T>),
+    GFA2Segment(GFA2Segment<N, T>),
+    Edge(Edge<N, T>),
+    Fragment(Fragment<N, T>),
+    Gap(Gap<N, T>),
+    OrderedGroup(OrderedGroup<N, T>),
+    UnorderedGroup(UnorderedGroup<N, T>),
+    /// A comment, empty line, or line with an unknown record type.
+    Raw(Vec<u8>),
+}
+
+/// The kinds of lines stored in a GFA, used to record the order of
+/// the lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LineKind {
+    Header,
+    Segment,
+    Link,
+    Jump,
+    Containment,
+    Path,
+    Walk,
+    GFA2Segment,
+    Edge,
+    Fragment,
+    Gap,
+    OrderedGroup,
+    UnorderedGroup,
+    Raw,
+}
+
+impl LineKind {
+    /// The line kinds in the order they're stored in the GFA, which
+    /// is the order lines are produced in if the line order isn't
+    /// known.
+    const ALL: [LineKind; 14] = [
+        LineKind::Header,
+        LineKind::Segment,
+        LineKind::Link,
+        LineKind::Jump,
+        LineKind::Containment,
+        LineKind::Path,
+        LineKind::Walk,
+        LineKind::GFA2Segment,
+        LineKind::Edge,
+        LineKind::Fragment,
+        LineKind::Gap,
+        LineKind::OrderedGroup,
+        LineKind::UnorderedGroup,
+        LineKind::Raw,
+    ];
+}
+
+impl<N, T: OptFields> Line<N, T> {
+    pub fn kind(&self) -> LineKind {
+        match self {
+            Line::Header(_) => LineKind::Header,
+            Line::Segment(_) => LineKind::Segment,
+            Line::Link(_) => LineKind::Link,
+            Line::Jump(_) => LineKind::Jump,
+            Line::Containment(_) => LineKind::Containment,
+            Line::Path(_) => LineKind::Path,
+            Line::Walk(_) => LineKind::Walk,
+            Line::GFA2Segment(_) => LineKind::GFA2Segment,
+            Line::Edge(_) => LineKind::Edge,
+            Line::Fragment(_) => LineKind::Fragment,
+            Line::Gap(_) => LineKind::Gap,
+            Line::OrderedGroup(_) => LineKind::OrderedGroup,
+            Line::UnorderedGroup(_) => LineKind::UnorderedGroup,
+            Line::Raw(_) => LineKind::Raw,
+        }
+    }
+
+    /// Borrow the contents of the line.
+    pub fn as_line_ref(&self) -> LineRef<'_, N, T> {
+        match self {
+            Line::Header(x) => LineRef::Header(x),
+            Line::Segment(x) => LineRef::Segment(x),
+            Line::Link(x) => LineRef::Link(x),
+            Line::Jump(x) => LineRef::Jump(x),
+            Line::Containment(x) => LineRef::Containment(x),
+            Line::Path(x) => LineRef::Path(x),
+            Line::Walk(x) => LineRef::Walk(x),
+            Line::GFA2Segment(x) => LineRef::GFA2Segment(x),
+            Line::Edge(x) => LineRef::Edge(x),
+            Line::Fragment(x) => LineRef::Fragment(x),
+            Line::Gap(x) => LineRef::Gap(x),
+            Line::OrderedGroup(x) => LineRef::OrderedGroup(x),
+            Line::UnorderedGroup(x) => LineRef::UnorderedGroup(x),
+            Line::Raw(x) => LineRef::Raw(x),
+        }
+    }
 }
 
 macro_rules! some_line_fn {
@@ -52,8 +199,20 @@ macro_rules! some_line_fn {
 some_line_fn!(some_header, Header<T>, Line::Header);
 some_line_fn!(some_segment, Segment<N, T>, Line::Segment);
 some_line_fn!(some_link, Link<N, T>, Line::Link);
+some_line_fn!(some_jump, Jump<N, T>, Line::Jump);
 some_line_fn!(some_containment, Containment<N, T>, Line::Containment);
 some_line_fn!(some_path, Path<N, T>, Line::Path);
+some_line_fn!(some_walk, Walk<N, T>, Line::Walk);
+some_line_fn!(some_gfa2_segment, GFA2Segment<N, T>, Line::GFA2Segment);
+some_line_fn!(some_edge, Edge<N, T>, Line::Edge);
+some_line_fn!(some_fragment, Fragment<N, T>, Line::Fragment);
+some_line_fn!(some_gap, Gap<N, T>, Line::Gap);
+some_line_fn!(some_ordered_group, OrderedGroup<N, T>, Line::OrderedGroup);
+some_line_fn!(
+    some_unordered_group,
+    UnorderedGroup<N, T>,
+    Line::UnorderedGroup
+);
 
 macro_rules! some_line_ref_fn {
     ($name:ident, $tgt:ty, $variant:path) => {
@@ -72,57 +231,315 @@ macro_rules! some_line_ref_fn {
 some_line_ref_fn!(some_header, Header<T>, LineRef::Header);
 some_line_ref_fn!(some_segment, Segment<N, T>, LineRef::Segment);
 some_line_ref_fn!(some_link, Link<N, T>, LineRef::Link);
+some_line_ref_fn!(some_jump, Jump<N, T>, LineRef::Jump);
 some_line_ref_fn!(some_containment, Containment<N, T>, LineRef::Containment);
 some_line_ref_fn!(some_path, Path<N, T>, LineRef::Path);
+some_line_ref_fn!(some_walk, Walk<N, T>, LineRef::Walk);
+some_line_ref_fn!(some_gfa2_segment, GFA2Segment<N, T>, LineRef::GFA2Segment);
+some_line_ref_fn!(some_edge, Edge<N, T>, LineRef::Edge);
+some_line_ref_fn!(some_fragment, Fragment<N, T>, LineRef::Fragment);
+some_line_ref_fn!(some_gap, Gap<N, T>, LineRef::Gap);
+some_line_ref_fn!(
+    some_ordered_group,
+    OrderedGroup<N, T>,
+    LineRef::OrderedGroup
+);
+some_line_ref_fn!(
+    some_unordered_group,
+    UnorderedGroup<N, T>,
+    LineRef::UnorderedGroup
+);
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum LineRef<'a, N, T: OptFields> {
     Header(&'a Header<T>),
     Segment(&'a Segment<N, T>),
     Link(&'a Link<N, T>),
+    Jump(&'a Jump<N, T>),
     Containment(&'a Containment<N, T>),
     Path(&'a Path<N, T>),
+    Walk(&'a Walk<N, T>),
+    GFA2Segment(&'a GFA2Segment<N, T>),
+    Edge(&'a Edge<N, T>),
+    Fragment(&'a Fragment<N, T>),
+    Gap(&'a Gap<N, T>),
+    OrderedGroup(&'a OrderedGroup<N, T>),
+    UnorderedGroup(&'a UnorderedGroup<N, T>),
+    Raw(&'a [u8]),
 }
 
 impl<N, T: OptFields> GFA<N, T> {
     /// Insert a GFA line (wrapped in the Line enum) into an existing
     /// GFA. Simply pushes it into the corresponding Vec in the GFA,
-    /// or replaces the header, so there's no deduplication or sorting
-    /// taking place.
+    /// or merges it into the header, so there's no deduplication or
+    /// sorting taking place. If the GFA keeps track of the line
+    /// order, the line is added to the end of it.
     #[inline]
     pub fn insert_line(&mut self, line: Line<N, T>) {
         use Line::*;
+        let kind = line.kind();
+        let index = match kind {
+            LineKind::Header => 0,
+            _ => self.line_count(kind),
+        };
         match line {
-            Header(h) => self.header = h,
+            Header(h) => self.header.merge(h),
             Segment(s) => self.segments.push(s),
             Link(s) => self.links.push(s),
+            Jump(s) => self.jumps.push(s),
             Containment(s) => self.containments.push(s),
             Path(s) => self.paths.push(s),
+            Walk(s) => self.walks.push(s),
+            GFA2Segment(s) => self.gfa2_segments.push(s),
+            Edge(s) => self.edges.push(s),
+            Fragment(s) => self.fragments.push(s),
+            Gap(s) => self.gaps.push(s),
+            OrderedGroup(s) => self.ordered_groups.push(s),
+            UnorderedGroup(s) => self.unordered_groups.push(s),
+            Raw(s) => self.raw_lines.push(s),
+        }
+        if let Some(order) = &mut self.line_order {
+            // There's only one header, which stays where it was first
+            // inserted
+            if kind != LineKind::Header || !order.contains(&(kind, 0)) {
+                order.push((kind, index));
+            }
+        }
+    }
+
+    /// The version of the GFA, which is the version in the header, if
+    /// it's a known version, and otherwise detected from the kinds of
+    /// lines in the GFA.
+    pub fn version(&self) -> GFAVersion {
+        let version = self.header.version.as_deref();
+        version.and_then(GFAVersion::from_bytes).unwrap_or_else(|| {
+            let kinds = LineKind::ALL.iter().copied();
+            GFAVersion::detect(kinds.filter(|&k| self.line_count(k) > 0))
+        })
+    }
+
+    /// The number of lines of the given kind. There's always one
+    /// header.
+    pub fn line_count(&self, kind: LineKind) -> usize {
+        match kind {
+            LineKind::Header => 1,
+            LineKind::Segment => self.segments.len(),
+            LineKind::Link => self.links.len(),
+            LineKind::Jump => self.jumps.len(),
+            LineKind::Containment => self.containments.len(),
+            LineKind::Path => self.paths.len(),
+            LineKind::Walk => self.walks.len(),
+            LineKind::GFA2Segment => self.gfa2_segments.len(),
+            LineKind::Edge => self.edges.len(),
+            LineKind::Fragment => self.fragments.len(),
+            LineKind::Gap => self.gaps.len(),
+            LineKind::OrderedGroup => self.ordered_groups.len(),
+            LineKind::UnorderedGroup => self.unordered_groups.len(),
+            LineKind::Raw => self.raw_lines.len(),
+        }
+    }
+
+    /// Get a reference to the line of the given kind at the index.
+    pub fn get_line(
+        &self,
+        kind: LineKind,
+        index: usize,
+    ) -> Option<LineRef<'_, N, T>> {
+        use LineRef::*;
+        match kind {
+            LineKind::Header => {
+                Some(Header(&self.header)).filter(|_| index == 0)
+            }
+            LineKind::Segment => self.segments.get(index).map(Segment),
+            LineKind::Link => self.links.get(index).map(Link),
+            LineKind::Jump => self.jumps.get(index).map(Jump),
+            LineKind::Containment => {
+                self.containments.get(index).map(Containment)
+            }
+            LineKind::Path => self.paths.get(index).map(Path),
+            LineKind::Walk => self.walks.get(index).map(Walk),
+            LineKind::GFA2Segment => {
+                self.gfa2_segments.get(index).map(GFA2Segment)
+            }
+            LineKind::Edge => self.edges.get(index).map(Edge),
+            LineKind::Fragment => self.fragments.get(index).map(Fragment),
+            LineKind::Gap => self.gaps.get(index).map(Gap),
+            LineKind::OrderedGroup => {
+                self.ordered_groups.get(index).map(OrderedGroup)
+            }
+            LineKind::UnorderedGroup => {
+                self.unordered_groups.get(index).map(UnorderedGroup)
+            }
+            LineKind::Raw => self.raw_lines.get(index).map(|l| Raw(l)),
         }
     }
 
+    /// Start keeping track of the order lines are inserted in, if
+    /// the GFA doesn't already. Lines that are already in the GFA are
+    /// treated as having been inserted in the default order.
+    pub fn track_line_order(&mut self) {
+        if self.line_order.is_none() {
+            let header = std::iter::once((LineKind::Header, 0));
+            self.line_order =
+                Some(header.chain(self.line_positions()).collect());
+        }
+    }
+
+    /// The kind and index of every line in the GFA, in the recorded
+    /// line order, if there is one. Lines that were removed since are
+    /// skipped, and lines that were added without being recorded come
+    /// last, in the default order.
+    ///
+    /// The header is only included if it's in the recorded order.
+    /// Without a recorded order, the lines are in the order of
+    /// `lines_iter`, followed by any raw lines.
+    pub fn line_positions(
+        &self,
+    ) -> impl Iterator<Item = (LineKind, usize)> + '_ {
+        let mut recorded = [0usize; LineKind::ALL.len()];
+        let order = self.line_order.as_deref().unwrap_or(&[]);
+        for &(kind, index) in order {
+            let count = &mut recorded[kind as usize];
+            *count = (*count).max(index + 1);
+        }
+
+        let ordered = order
+            .iter()
+            .copied()
+            .filter(move |&(kind, index)| index < self.line_count(kind));
+        let rest = LineKind::ALL
+            .iter()
+            .copied()
+            .filter(|&kind| kind != LineKind::Header)
+            .flat_map(move |kind| {
+                (recorded[kind as usize]..self.line_count(kind))
+                    .map(move |index| (kind, index))
+            });
+        ordered.chain(rest)
+    }
+
     /// Consume a GFA object to produce an iterator over all the lines
     /// contained within. The iterator first produces all segments, then
-    /// links, then containments, and finally paths.
+    /// links and jumps, then containments, and then paths and walks,
+    /// followed by the GFA2 segments, edges, fragments, gaps, and
+    /// groups.
+    ///
+    /// If the GFA keeps track of the line order, the lines, including
+    /// the header and raw lines, are instead produced in that order,
+    /// as given by `line_positions`.
     pub fn lines_into_iter(self) -> impl Iterator<Item = Line<N, T>> {
         use Line::*;
+
+        let order: Option<Vec<_>> = self
+            .line_order
+            .as_ref()
+            .map(|_| self.line_positions().collect());
+
+        // The index of the first line of each kind in the lines below
+        let mut offsets = [0usize; LineKind::ALL.len()];
+        let mut total = 1;
+        for &kind in LineKind::ALL.iter().skip(1) {
+            offsets[kind as usize] = total;
+            total += self.line_count(kind);
+        }
+
+        let header = std::iter::once(Header(self.header));
         let segs = self.segments.into_iter().map(Segment);
         let links = self.links.into_iter().map(Link);
+        let jumps = self.jumps.into_iter().map(Jump);
         let conts = self.containments.into_iter().map(Containment);
         let paths = self.paths.into_iter().map(Path);
+        let walks = self.walks.into_iter().map(Walk);
+
+        let segs2 = self.gfa2_segments.into_iter().map(GFA2Segment);
+        let edges = self.edges.into_iter().map(Edge);
+        let frags = self.fragments.into_iter().map(Fragment);
+        let gaps = self.gaps.into_iter().map(Gap);
+        let o_groups = self.ordered_groups.into_iter().map(OrderedGroup);
+        let u_groups = self.unordered_groups.into_iter().map(UnorderedGroup);
+        let raw = self.raw_lines.into_iter().map(Raw);
+
+        let lines = header
+            .chain(segs)
+            .chain(links)
+            .chain(jumps)
+            .chain(conts)
+            .chain(paths)
+            .chain(walks)
+            .chain(segs2)
+            .chain(edges)
+            .chain(frags)
+            .chain(gaps)
+            .chain(o_groups)
+            .chain(u_groups)
+            .chain(raw);
+
+        let (ordered, unordered) = match order {
+            Some(order) => {
+                let mut lines: Vec<_> = lines.map(Some).collect();
+                let ordered =
+                    order.into_iter().filter_map(move |(kind, ix)| {
+                        lines[offsets[kind as usize] + ix].take()
+                    });
+                (Some(ordered), None)
+            }
+            // The header and raw lines aren't included by default
+            None => {
+                let count = offsets[LineKind::Raw as usize] - 1;
+                (None, Some(lines.skip(1).take(count)))
+            }
+        };
 
-        segs.chain(links).chain(conts).chain(paths)
+        ordered
+            .into_iter()
+            .flatten()
+            .chain(unordered.into_iter().flatten())
     }
 
-    /// Return an iterator over references to the lines in the GFA
+    /// Return an iterator over references to the lines in the GFA.
+    ///
+    /// If the GFA keeps track of the line order, the lines, including
+    /// the header and raw lines, are produced in that order, as
+    /// given by `line_positions`.
     pub fn lines_iter(&'_ self) -> impl Iterator<Item = LineRef<'_, N, T>> {
         use LineRef::*;
         let segs = self.segments.iter().map(Segment);
         let links = self.links.iter().map(Link);
+        let jumps = self.jumps.iter().map(Jump);
         let conts = self.containments.iter().map(Containment);
         let paths = self.paths.iter().map(Path);
+        let walks = self.walks.iter().map(Walk);
+
+        let segs2 = self.gfa2_segments.iter().map(GFA2Segment);
+        let edges = self.edges.iter().map(Edge);
+        let frags = self.fragments.iter().map(Fragment);
+        let gaps = self.gaps.iter().map(Gap);
+        let o_groups = self.ordered_groups.iter().map(OrderedGroup);
+        let u_groups = self.unordered_groups.iter().map(UnorderedGroup);
+
+        let unordered = segs
+            .chain(links)
+            .chain(jumps)
+            .chain(conts)
+            .chain(paths)
+            .chain(walks)
+            .chain(segs2)
+            .chain(edges)
+            .chain(frags)
+            .chain(gaps)
+            .chain(o_groups)
+            .chain(u_groups);
 
-        segs.chain(links).chain(conts).chain(paths)
+        let ordered = self.line_order.as_ref().map(|_| {
+            self.line_positions()
+                .filter_map(move |(kind, ix)| self.get_line(kind, ix))
+        });
+        let unordered = Some(unordered).filter(|_| ordered.is_none());
+
+        ordered
+            .into_iter()
+            .flatten()
+            .chain(unordered.into_iter().flatten())
     }
 }
 
@@ -130,6 +547,15 @@ impl<N: SegmentId, T: OptFields> GFA<N, T> {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Create an empty GFA that keeps track of the order lines are
+    /// inserted in.
+    pub fn with_line_order() -> Self {
+        GFA {
+            line_order: Some(Vec::new()),
+            ..Default::default()
+        }
+    }
 }
 
 /// The header line of a GFA graph
@@ -139,6 +565,18 @@ pub struct Header<T: OptFields> {
     pub optional: T,
 }
 
+impl<T: OptFields> Header<T> {
+    /// Merge another header line into this one, so that a GFA with
+    /// several header lines has a single header. The version and tags
+    /// of `other` replace those in this header.
+    pub fn merge(&mut self, other: Header<T>) {
+        if other.version.is_some() {
+            self.version = other.version;
+        }
+        self.optional.merge(other.optional);
+    }
+}
+
 impl<T: OptFields> Default for Header<T> {
     fn default() -> Self {
         Header {
@@ -200,6 +638,51 @@ impl<T: OptFields> Link<Vec<u8>, T> {
     }
 }
 
+/// A jump, as introduced in GFA 1.2, connecting two segments across
+/// a scaffolding gap. The distance is `None` if it was given as `*`.
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Jump<N, T: OptFields> {
+    pub from_segment: N,
+    pub from_orient: Orientation,
+    pub to_segment: N,
+    pub to_orient: Orientation,
+    pub distance: Option<i64>,
+    pub optional: T,
+}
+
+impl<T: OptFields> Jump<Vec<u8>, T> {
+    #[inline]
+    pub fn new(
+        from_segment: &[u8],
+        from_orient: Orientation,
+        to_segment: &[u8],
+        to_orient: Orientation,
+        distance: Option<i64>,
+    ) -> Jump<Vec<u8>, T> {
+        Jump {
+            from_segment: from_segment.into(),
+            from_orient,
+            to_segment: to_segment.into(),
+            to_orient,
+            distance,
+            optional: Default::default(),
+        }
+    }
+}
+
+impl<N, T: OptFields> Jump<N, T> {
+    /// Returns true if the jump has the `SC:i:1` tag, marking it as a
+    /// shortcut that doesn't describe a scaffolding gap. This is
+    /// always false if the optional fields aren't parsed.
+    pub fn is_shortcut(&self) -> bool {
+        self.optional
+            .get_field(b"SC")
+            .and_then(|f| f.get_int())
+            .is_some_and(|sc| sc == 1)
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
 #[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
 pub struct Containment<N, T: OptFields> {
@@ -214,7 +697,8 @@ pub struct Containment<N, T: OptFields> {
 
 /// The step list that the path actually consists of is an unparsed
 /// Vec<u8> to keep memory down; use path.iter() to get an iterator
-/// over the parsed path segments and orientations.
+/// over the parsed path segments and orientations. Steps are
+/// separated by `,`, or by `;` if they are connected by a jump.
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
 #[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
 pub struct Path<N, T: OptFields> {
@@ -243,6 +727,24 @@ impl<N: SegmentId, T: OptFields> Path<N, T> {
     }
 }
 
+impl<N, T: OptFields> Path<N, T> {
+    /// Produces an iterator over the separators between the steps of
+    /// the path, i.e. `,` for steps connected by links, and `;` for
+    /// steps connected by jumps.
+    #[inline]
+    pub fn separators(&self) -> impl Iterator<Item = u8> + '_ {
+        self.segment_names
+            .iter()
+            .copied()
+            .filter(|&b| is_step_separator(b))
+    }
+}
+
+#[inline]
+pub(crate) fn is_step_separator(b: u8) -> bool {
+    b == b',' || b == b';'
+}
+
 impl<N: SegmentId, T: OptFields> Path<N, T> {
     /// Parses (and copies!) a segment ID in the path segment list
     #[inline]
@@ -266,7 +768,7 @@ impl<T: OptFields> Path<Vec<u8>, T> {
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = (&'_ BStr, Orientation)> {
         self.segment_names
-            .split_str(b",")
+            .split(|&b| is_step_separator(b))
             .filter_map(Self::segment_id_ref)
     }
 
@@ -290,11 +792,268 @@ impl<T: OptFields> Path<usize, T> {
         &'a self,
     ) -> impl Iterator<Item = (usize, Orientation)> + 'a {
         self.segment_names
-            .split_str(b",")
+            .split(|&b| is_step_separator(b))
             .filter_map(Self::parse_segment_id)
     }
 }
 
+/// A walk, as introduced in GFA 1.1, describing a haplotype sequence
+/// as a walk through the graph. The start and end coordinates are
+/// `None` if they were given as `*`. As with paths, the list of steps
+/// is stored unparsed; use walk.iter() to get an iterator over the
+/// parsed steps.
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Walk<N, T: OptFields> {
+    pub sample_id: Vec<u8>,
+    pub hap_index: usize,
+    pub seq_id: Vec<u8>,
+    pub seq_start: Option<usize>,
+    pub seq_end: Option<usize>,
+    pub walk: Vec<u8>,
+    pub optional: T,
+    _segment_names: std::marker::PhantomData<N>,
+}
+
+impl<N: SegmentId, T: OptFields> Walk<N, T> {
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        sample_id: Vec<u8>,
+        hap_index: usize,
+        seq_id: Vec<u8>,
+        seq_start: Option<usize>,
+        seq_end: Option<usize>,
+        walk: Vec<u8>,
+        optional: T,
+    ) -> Self {
+        Walk {
+            sample_id,
+            hap_index,
+            seq_id,
+            seq_start,
+            seq_end,
+            walk,
+            optional,
+            _segment_names: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Produces an iterator over the steps of a walk string such as
+/// `>s1<s2>s3`, yielding each segment name along with the orientation
+/// parsed from the preceding `>` or `<`. Stops at the first step that
+/// doesn't start with an orientation.
+#[inline]
+pub(crate) fn walk_steps(
+    walk: &[u8],
+) -> impl Iterator<Item = (&'_ [u8], Orientation)> {
+    let mut rest = walk;
+    std::iter::from_fn(move || {
+        let (first, tail) = rest.split_first()?;
+        let orient = Orientation::from_bytes_gt_ln([*first])?;
+        let end = tail
+            .iter()
+            .position(|&b| b == b'>' || b == b'<')
+            .unwrap_or(tail.len());
+        let (name, next) = tail.split_at(end);
+        rest = next;
+        Some((name, orient))
+    })
+}
+
+impl<T: OptFields> Walk<Vec<u8>, T> {
+    /// Produces an iterator over the steps of the given walk, parsing
+    /// the orientation and producing a slice to each segment name
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&'_ BStr, Orientation)> {
+        walk_steps(&self.walk).map(|(name, orient)| (name.as_bstr(), orient))
+    }
+}
+
+impl<T: OptFields> Walk<usize, T> {
+    /// Produces an iterator over the usize segments of the given
+    /// walk.
+    #[inline]
+    pub fn iter<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (usize, Orientation)> + 'a {
+        walk_steps(&self.walk)
+            .filter_map(|(name, orient)| Some((usize::parse_id(name)?, orient)))
+    }
+}
+
+/// A GFA2 segment. Unlike GFA1 segments, the length of the segment
+/// is a required field, and the sequence is often `*`.
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct GFA2Segment<N, T: OptFields> {
+    pub name: N,
+    pub length: usize,
+    pub sequence: Vec<u8>,
+    pub optional: T,
+}
+
+impl<T: OptFields> GFA2Segment<Vec<u8>, T> {
+    #[inline]
+    pub fn new(name: &[u8], length: usize, sequence: &[u8]) -> Self {
+        GFA2Segment {
+            name: Vec::from(name),
+            length,
+            sequence: Vec::from(sequence),
+            optional: Default::default(),
+        }
+    }
+}
+
+/// A GFA2 edge. The ID is `None` if the edge was given the `*` ID.
+/// The alignment is stored unparsed, as it can be either a CIGAR
+/// string, a trace, or `*`.
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Edge<N, T: OptFields> {
+    pub id: Option<Vec<u8>>,
+    pub sid1: N,
+    pub sid1_orient: Orientation,
+    pub sid2: N,
+    pub sid2_orient: Orientation,
+    pub beg1: Position,
+    pub end1: Position,
+    pub beg2: Position,
+    pub end2: Position,
+    pub alignment: Vec<u8>,
+    pub optional: T,
+}
+
+/// A GFA2 fragment, describing how an external sequence (e.g. a
+/// read) aligns to a segment.
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Fragment<N, T: OptFields> {
+    pub segment: N,
+    pub external: Vec<u8>,
+    pub external_orient: Orientation,
+    pub sbeg: Position,
+    pub send: Position,
+    pub fbeg: Position,
+    pub fend: Position,
+    pub alignment: Vec<u8>,
+    pub optional: T,
+}
+
+/// A GFA2 gap between two segments. The variance is `None` if it was
+/// given as `*`.
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Gap<N, T: OptFields> {
+    pub id: Option<Vec<u8>>,
+    pub sid1: N,
+    pub sid1_orient: Orientation,
+    pub sid2: N,
+    pub sid2_orient: Orientation,
+    pub distance: i64,
+    pub variance: Option<i64>,
+    pub optional: T,
+}
+
+/// A GFA2 ordered group (O-line). Like the path segment list, the
+/// space-separated list of oriented references is stored unparsed;
+/// use group.iter() to get an iterator over the parsed references.
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct OrderedGroup<N, T: OptFields> {
+    pub id: Option<Vec<u8>>,
+    pub items: Vec<u8>,
+    pub optional: T,
+    _segment_names: std::marker::PhantomData<N>,
+}
+
+/// A GFA2 unordered group (U-line). The space-separated list of
+/// references is stored unparsed; use group.iter() to get an
+/// iterator over the references.
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct UnorderedGroup<N, T: OptFields> {
+    pub id: Option<Vec<u8>>,
+    pub items: Vec<u8>,
+    pub optional: T,
+    _segment_names: std::marker::PhantomData<N>,
+}
+
+impl<N: SegmentId, T: OptFields> OrderedGroup<N, T> {
+    #[inline]
+    pub fn new(id: Option<Vec<u8>>, items: Vec<u8>, optional: T) -> Self {
+        OrderedGroup {
+            id,
+            items,
+            optional,
+            _segment_names: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<N: SegmentId, T: OptFields> UnorderedGroup<N, T> {
+    #[inline]
+    pub fn new(id: Option<Vec<u8>>, items: Vec<u8>, optional: T) -> Self {
+        UnorderedGroup {
+            id,
+            items,
+            optional,
+            _segment_names: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T: OptFields> OrderedGroup<Vec<u8>, T> {
+    /// Produces an iterator over the references in the group,
+    /// parsing the orientation and producing a slice to each name
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&'_ BStr, Orientation)> {
+        self.items.split_str(b" ").filter_map(|item| {
+            let (name, orient) = split_orientation(item)?;
+            Some((name.as_bstr(), orient))
+        })
+    }
+}
+
+impl<T: OptFields> OrderedGroup<usize, T> {
+    /// Produces an iterator over the usize references in the group.
+    #[inline]
+    pub fn iter<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (usize, Orientation)> + 'a {
+        self.items.split_str(b" ").filter_map(|item| {
+            let (name, orient) = split_orientation(item)?;
+            Some((usize::parse_id(name)?, orient))
+        })
+    }
+}
+
+/// Splits a GFA2 reference, i.e. an ID immediately followed by `+` or
+/// `-`, into the ID and the orientation.
+#[inline]
+fn split_orientation(input: &[u8]) -> Option<(&[u8], Orientation)> {
+    let (last, name) = input.split_last()?;
+    let orient = Orientation::from_bytes_plus_minus([*last])?;
+    Some((name, orient))
+}
+
+impl<T: OptFields> UnorderedGroup<Vec<u8>, T> {
+    /// Produces an iterator over the names in the group
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &'_ BStr> {
+        self.items.split_str(b" ").map(|s| s.as_bstr())
+    }
+}
+
+impl<T: OptFields> UnorderedGroup<usize, T> {
+    /// Produces an iterator over the usize names in the group
+    #[inline]
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = usize> + 'a {
+        self.items.split_str(b" ").filter_map(usize::parse_id)
+    }
+}
+
 impl<N, T: OptFields> Segment<N, T> {
     pub(crate) fn nameless_clone<M: Default>(&self) -> Segment<M, T> {
         Segment {
@@ -318,6 +1077,19 @@ impl<N, T: OptFields> Link<N, T> {
     }
 }
 
+impl<N, T: OptFields> Jump<N, T> {
+    pub(crate) fn nameless_clone<M: Default>(&self) -> Jump<M, T> {
+        Jump {
+            from_segment: Default::default(),
+            from_orient: self.from_orient,
+            to_segment: Default::default(),
+            to_orient: self.to_orient,
+            distance: self.distance,
+            optional: self.optional.clone(),
+        }
+    }
+}
+
 impl<N, T: OptFields> Containment<N, T> {
     pub(crate) fn nameless_clone<M: Default>(&self) -> Containment<M, T> {
         Containment {
@@ -332,15 +1104,243 @@ impl<N, T: OptFields> Containment<N, T> {
     }
 }
 
+impl<N, T: OptFields> GFA2Segment<N, T> {
+    pub(crate) fn nameless_clone<M: Default>(&self) -> GFA2Segment<M, T> {
+        GFA2Segment {
+            name: Default::default(),
+            length: self.length,
+            sequence: self.sequence.clone(),
+            optional: self.optional.clone(),
+        }
+    }
+}
+
+impl<N, T: OptFields> Edge<N, T> {
+    pub(crate) fn nameless_clone<M: Default>(&self) -> Edge<M, T> {
+        Edge {
+            id: self.id.clone(),
+            sid1: Default::default(),
+            sid1_orient: self.sid1_orient,
+            sid2: Default::default(),
+            sid2_orient: self.sid2_orient,
+            beg1: self.beg1,
+            end1: self.end1,
+            beg2: self.beg2,
+            end2: self.end2,
+            alignment: self.alignment.clone(),
+            optional: self.optional.clone(),
+        }
+    }
+}
+
+impl<N, T: OptFields> Fragment<N, T> {
+    pub(crate) fn nameless_clone<M: Default>(&self) -> Fragment<M, T> {
+        Fragment {
+            segment: Default::default(),
+            external: self.external.clone(),
+            external_orient: self.external_orient,
+            sbeg: self.sbeg,
+            send: self.send,
+            fbeg: self.fbeg,
+            fend: self.fend,
+            alignment: self.alignment.clone(),
+            optional: self.optional.clone(),
+        }
+    }
+}
+
+impl<N, T: OptFields> Gap<N, T> {
+    pub(crate) fn nameless_clone<M: Default>(&self) -> Gap<M, T> {
+        Gap {
+            id: self.id.clone(),
+            sid1: Default::default(),
+            sid1_orient: self.sid1_orient,
+            sid2: Default::default(),
+            sid2_orient: self.sid2_orient,
+            distance: self.distance,
+            variance: self.variance,
+            optional: self.optional.clone(),
+        }
+    }
+}
+
+// Generate getters for the values of reserved tags on a line type.
+// The getters return `None` if the tag is missing, or if its value
+// has another type than the one the tag is reserved with.
+macro_rules! reserved_tag_fns {
+    ($line:ident, $($(#[$meta:meta])* $name:ident, $tag:literal, $op:tt;)*) => {
+        impl<N, T: OptFields> $line<N, T> {
+            $(reserved_tag_fn!($(#[$meta])* $name, $tag, $op);)*
+        }
+    };
+}
+
+macro_rules! reserved_tag_fn {
+    ($(#[$meta:meta])* $name:ident, $tag:literal, int) => {
+        $(#[$meta])*
+        pub fn $name(&self) -> Option<i64> {
+            self.optional.get_field($tag)?.get_int()
+        }
+    };
+    ($(#[$meta:meta])* $name:ident, $tag:literal, string) => {
+        $(#[$meta])*
+        pub fn $name(&self) -> Option<Vec<u8>> {
+            self.optional.get_field($tag)?.get_string().map(Vec::from)
+        }
+    };
+    ($(#[$meta:meta])* $name:ident, $tag:literal, bytes) => {
+        $(#[$meta])*
+        pub fn $name(&self) -> Option<Vec<u8>> {
+            self.optional.get_field($tag)?.get_byte_array().map(Vec::from)
+        }
+    };
+}
+
+reserved_tag_fns!(
+    Segment,
+    /// The length of the segment in the `LN` tag, which can differ
+    /// from the length of the sequence, e.g. if it's `*`.
+    length_tag, b"LN", int;
+    /// The `RC` read count.
+    read_count, b"RC", int;
+    /// The `FC` fragment count.
+    fragment_count, b"FC", int;
+    /// The `KC` k-mer count.
+    kmer_count, b"KC", int;
+    /// The `SH` SHA-256 checksum of the sequence.
+    checksum, b"SH", bytes;
+    /// The `UR` URI or local file system path of the sequence.
+    sequence_uri, b"UR", string;
+    /// The rGFA `SN` name of the stable sequence of the segment.
+    stable_name, b"SN", string;
+    /// The rGFA `SO` offset of the segment on its stable sequence.
+    stable_offset, b"SO", int;
+    /// The rGFA `SR` rank of the stable sequence of the segment.
+    stable_rank, b"SR", int;
+);
+
+reserved_tag_fns!(
+    Link,
+    /// The `MQ` mapping quality.
+    mapping_quality, b"MQ", int;
+    /// The `NM` number of mismatches/gaps.
+    mismatch_count, b"NM", int;
+    /// The `RC` read count.
+    read_count, b"RC", int;
+    /// The `FC` fragment count.
+    fragment_count, b"FC", int;
+    /// The `KC` k-mer count.
+    kmer_count, b"KC", int;
+    /// The `ID` edge identifier.
+    edge_id, b"ID", string;
+);
+
+reserved_tag_fns!(
+    Containment,
+    /// The `RC` read count.
+    read_count, b"RC", int;
+    /// The `NM` number of mismatches/gaps.
+    mismatch_count, b"NM", int;
+    /// The `ID` edge identifier.
+    edge_id, b"ID", string;
+);
+
+impl<T: OptFields> Header<T> {
+    /// The `TS` trace spacing.
+    pub fn trace_spacing(&self) -> Option<i64> {
+        self.optional.get_field(b"TS")?.get_int()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn line_order() {
+        let mut gfa: GFA<Vec<u8>, ()> = GFA::with_line_order();
+        gfa.insert_line(Line::Raw(b"# comment".to_vec()));
+        gfa.insert_line(Line::Segment(Segment::new(b"1", b"A")));
+        gfa.insert_line(Line::Header(Header::default()));
+        gfa.insert_line(Line::Segment(Segment::new(b"2", b"C")));
+        gfa.insert_line(Line::Raw(b"# another".to_vec()));
+        gfa.insert_line(Line::Segment(Segment::new(b"3", b"G")));
+
+        let kinds: Vec<_> =
+            gfa.lines_iter().map(|l| l.some_segment()).collect();
+        assert_eq!(kinds.len(), 6);
+        assert_eq!(kinds.iter().flatten().count(), 3);
+
+        // Removed lines are skipped, and unrecorded lines come last
+        gfa.segments.pop();
+        gfa.segments.pop();
+        gfa.segments.push(Segment::new(b"4", b"T"));
+        gfa.segments.push(Segment::new(b"5", b"T"));
+        let positions: Vec<_> = gfa.line_positions().collect();
+        assert_eq!(
+            positions,
+            vec![
+                (LineKind::Raw, 0),
+                (LineKind::Segment, 0),
+                (LineKind::Header, 0),
+                (LineKind::Segment, 1),
+                (LineKind::Raw, 1),
+                (LineKind::Segment, 2),
+            ]
+        );
+
+        let lines: Vec<_> = gfa.clone().lines_into_iter().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], Line::Raw(b"# comment".to_vec()));
+        assert_eq!(lines[5].clone().some_segment().unwrap().name, b"5");
+
+        gfa.line_order = None;
+        assert_eq!(gfa.lines_iter().count(), 3);
+        assert_eq!(gfa.clone().lines_into_iter().count(), 3);
+        gfa.track_line_order();
+        assert_eq!(gfa.line_positions().count(), 6);
+    }
+
+    #[test]
+    fn new_gfa_has_empty_header() {
+        let gfa: GFA<Vec<u8>, ()> = GFA::new();
+        assert_eq!(gfa.header.version, None);
+        assert_eq!(Header::<()>::default().version, Some(b"1.0".to_vec()));
+
+        let mut gfa: GFA<Vec<u8>, ()> = GFA::new();
+        gfa.insert_line(Line::Header(Header::default()));
+        assert_eq!(gfa.header, Header::default());
+    }
+
+    #[test]
+    fn reserved_tag_getters() {
+        let mut segment: Segment<Vec<u8>, OptionalFields> =
+            Segment::new(b"1", b"*");
+        segment.optional = vec![
+            OptField::new(b"LN", OptFieldVal::Int(100)),
+            OptField::new(b"RC", OptFieldVal::Z(b"many".to_vec())),
+            OptField::new(b"SH", OptFieldVal::H(vec![0xAB, 0x01])),
+            OptField::new(b"SN", OptFieldVal::Z(b"chr1".to_vec())),
+        ];
+        assert_eq!(segment.length_tag(), Some(100));
+        // Values of the wrong type are left out
+        assert_eq!(segment.read_count(), None);
+        assert_eq!(segment.kmer_count(), None);
+        assert_eq!(segment.checksum(), Some(vec![0xAB, 0x01]));
+        assert_eq!(segment.stable_name(), Some(b"chr1".to_vec()));
+
+        let header: Header<OptionalFields> = Header {
+            version: None,
+            optional: vec![OptField::new(b"TS", OptFieldVal::Int(20))],
+        };
+        assert_eq!(header.trace_spacing(), Some(20));
+    }
+
     #[test]
     fn path_iter() {
         use Orientation::*;
 
-        let cigars = vec![b"4M", b"5M"]
+        let cigars = [b"4M", b"5M"]
             .iter()
             .map(|bs| CIGAR::from_bytestring(&bs[..]))
             .collect();
@@ -355,11 +1355,46 @@ mod tests {
         assert_eq!(None, path_iter.next());
     }
 
+    #[test]
+    fn path_iter_jumps() {
+        use Orientation::*;
+
+        let path: Path<usize, _> =
+            Path::new("14".into(), "11+;12-,13+".into(), vec![None], ());
+
+        let steps: Vec<_> = path.iter().collect();
+        assert_eq!(steps, vec![(11, Forward), (12, Backward), (13, Forward)]);
+
+        let seps: Vec<_> = path.separators().collect();
+        assert_eq!(seps, vec![b';', b',']);
+    }
+
+    #[test]
+    fn walk_iter() {
+        use Orientation::*;
+
+        let walk: Walk<Vec<u8>, _> = Walk::new(
+            "HG002".into(),
+            1,
+            "chr1".into(),
+            Some(0),
+            Some(12),
+            ">11<12>13".into(),
+            (),
+        );
+
+        let mut walk_iter = walk.iter();
+        assert_eq!(Some(("11".into(), Forward)), walk_iter.next());
+        assert_eq!(Some(("12".into(), Backward)), walk_iter.next());
+        assert_eq!(Some(("13".into(), Forward)), walk_iter.next());
+        assert_eq!(None, walk_iter.next());
+    }
+
     #[test]
     fn gfa_line_ref_iter() {
         let parser: crate::parser::GFAParser<usize, ()> =
             crate::parser::GFAParser::new();
-        let gfa = parser.parse_file(&"./test/gfas/lil.gfa").unwrap();
+        let gfa = parser.parse_file("./test/gfas/lil.gfa").unwrap();
         let gfa_lineref = gfa.lines_iter();
 
         for line in gfa_lineref {
@@ -0,0 +1,129 @@
+//! Borrowed versions of the most common GFA line types, which point
+//! into the line they were parsed from rather than copying its
+//! fields. Segment IDs and optional fields are left unparsed; use
+//! `to_owned()` to parse them and get the corresponding owned line.
+//!
+//! These are useful when scanning through large files, e.g. using
+//! `MmapGFA` or `LineIter::next_borrowed`, where most lines are only
+//! looked at once.
+
+use bstr::{BStr, ByteSlice};
+
+use crate::{
+    gfa::{is_step_separator, Orientation},
+    parser::scan::split_tabs,
+};
+
+/// Splits a tab-separated list of unparsed optional fields.
+#[inline]
+fn split_optional(optional: &[u8]) -> impl Iterator<Item = &[u8]> {
+    split_tabs(optional).filter(|field| !field.is_empty())
+}
+
+/// A GFA1 segment borrowing the line it was parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentRef<'a> {
+    pub name: &'a BStr,
+    pub sequence: &'a BStr,
+    /// The unparsed, tab-separated optional fields.
+    pub optional: &'a BStr,
+}
+
+impl<'a> SegmentRef<'a> {
+    /// Iterator over the unparsed optional fields.
+    #[inline]
+    pub fn optional_fields(&self) -> impl Iterator<Item = &'a [u8]> {
+        split_optional(self.optional)
+    }
+}
+
+/// A link borrowing the line it was parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkRef<'a> {
+    pub from_segment: &'a BStr,
+    pub from_orient: Orientation,
+    pub to_segment: &'a BStr,
+    pub to_orient: Orientation,
+    pub overlap: &'a BStr,
+    /// The unparsed, tab-separated optional fields.
+    pub optional: &'a BStr,
+}
+
+impl<'a> LinkRef<'a> {
+    /// Iterator over the unparsed optional fields.
+    #[inline]
+    pub fn optional_fields(&self) -> impl Iterator<Item = &'a [u8]> {
+        split_optional(self.optional)
+    }
+}
+
+/// A path borrowing the line it was parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathRef<'a> {
+    pub path_name: &'a BStr,
+    pub segment_names: &'a BStr,
+    /// The unparsed list of overlaps.
+    pub overlaps: &'a BStr,
+    /// The unparsed, tab-separated optional fields.
+    pub optional: &'a BStr,
+}
+
+impl<'a> PathRef<'a> {
+    /// Iterator over the unparsed optional fields.
+    #[inline]
+    pub fn optional_fields(&self) -> impl Iterator<Item = &'a [u8]> {
+        split_optional(self.optional)
+    }
+
+    /// Produces an iterator over the steps of the path, without
+    /// copying the segment names. Steps without a valid orientation
+    /// are skipped.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&'a BStr, Orientation)> {
+        self.segment_names
+            .split(|&b| is_step_separator(b))
+            .filter_map(|step| {
+                let (last, name) = step.split_last()?;
+                let orient = Orientation::from_bytes_plus_minus([*last])?;
+                Some((name.as_bstr(), orient))
+            })
+    }
+}
+
+/// The borrowed line types. Other line types aren't supported, and
+/// must be parsed into owned lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorrowedLine<'a> {
+    Segment(SegmentRef<'a>),
+    Link(LinkRef<'a>),
+    Path(PathRef<'a>),
+}
+
+impl<'a> BorrowedLine<'a> {
+    #[inline]
+    pub fn some_segment(self) -> Option<SegmentRef<'a>> {
+        if let BorrowedLine::Segment(x) = self {
+            Some(x)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn some_link(self) -> Option<LinkRef<'a>> {
+        if let BorrowedLine::Link(x) = self {
+            Some(x)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn some_path(self) -> Option<PathRef<'a>> {
+        if let BorrowedLine::Path(x) = self {
+            Some(x)
+        } else {
+            None
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    gfa::{Containment, Link, Path, Segment, GFA},
+    gfa::{
+        Containment, Edge, Fragment, GFA2Segment, Gap, Jump, Link,
+        OrderedGroup, Path, Segment, SegmentId, UnorderedGroup, Walk, GFA,
+    },
     optfields::*,
 };
 
@@ -43,9 +46,67 @@ fn hash_gfa<T: OptFields>(gfa: &GFA<Vec<u8>, T>) -> u64 {
         path.segment_names.hash(&mut hasher);
     }
 
+    for seg in gfa.gfa2_segments.iter() {
+        seg.name.hash(&mut hasher);
+        seg.length.hash(&mut hasher);
+        seg.sequence.hash(&mut hasher);
+    }
+    for edge in gfa.edges.iter() {
+        edge.id.hash(&mut hasher);
+        edge.sid1.hash(&mut hasher);
+        edge.sid1_orient.hash(&mut hasher);
+        edge.sid2.hash(&mut hasher);
+        edge.sid2_orient.hash(&mut hasher);
+        edge.alignment.hash(&mut hasher);
+    }
+    for frag in gfa.fragments.iter() {
+        frag.segment.hash(&mut hasher);
+        frag.external.hash(&mut hasher);
+        frag.external_orient.hash(&mut hasher);
+        frag.alignment.hash(&mut hasher);
+    }
+    for gap in gfa.gaps.iter() {
+        gap.id.hash(&mut hasher);
+        gap.sid1.hash(&mut hasher);
+        gap.sid2.hash(&mut hasher);
+        gap.distance.hash(&mut hasher);
+    }
+    for group in gfa.ordered_groups.iter() {
+        group.id.hash(&mut hasher);
+        group.items.hash(&mut hasher);
+    }
+    for group in gfa.unordered_groups.iter() {
+        group.id.hash(&mut hasher);
+        group.items.hash(&mut hasher);
+    }
+
     hasher.finish()
 }
 
+/// Rebuilds the space-separated references of a GFA2 group with
+/// each name replaced by the result of `map`. If `oriented` is true,
+/// every reference ends with an orientation, which is kept as is.
+fn map_group_items<F>(items: &[u8], oriented: bool, map: F) -> Option<Vec<u8>>
+where
+    F: Fn(&[u8]) -> Option<Vec<u8>>,
+{
+    let mut new_items: Vec<u8> = Vec::with_capacity(items.len());
+    for (i, item) in items.split_str(b" ").enumerate() {
+        let (name, orient) = if oriented {
+            let (last, name) = item.split_last()?;
+            (name, Some(*last))
+        } else {
+            (item, None)
+        };
+        if i != 0 {
+            new_items.push(b' ');
+        }
+        new_items.extend(map(name)?);
+        new_items.extend(orient);
+    }
+    Some(new_items)
+}
+
 /// This is a helper struct for handling serialization/deserialization
 /// of NameMaps to text-based formats such as ASCII
 #[cfg(feature = "serde1")]
@@ -150,30 +211,17 @@ impl NameMap {
         &self,
         path: &Path<Vec<u8>, T>,
     ) -> Option<Path<usize, T>> {
-        let mut misses = 0;
-        let new_segs: Vec<u8> = path
-            .iter()
-            .filter_map(|(seg, o)| {
-                let n = self.map_name(seg).map(|s| (s, o));
-                if n.is_none() {
-                    misses += 1;
-                }
-                n
-            })
-            .enumerate()
-            .flat_map(|(i, (seg, o))| {
-                let s = if i == 0 {
-                    format!("{}{}", seg, o)
-                } else {
-                    format!(",{}{}", seg, o)
-                };
-                Vec::from(s.as_bytes())
-            })
-            .collect();
-
-        if misses > 0 {
-            return None;
+        let mut new_segs: Vec<u8> =
+            Vec::with_capacity(path.segment_names.len());
+        let mut separators = path.separators();
+        for (i, (seg, o)) in path.iter().enumerate() {
+            let id = self.map_name(seg)?;
+            if i != 0 {
+                new_segs.push(separators.next().unwrap_or(b','));
+            }
+            new_segs.extend(format!("{}{}", id, o).bytes());
         }
+
         let new_path = Path::new(
             path.path_name.clone(),
             new_segs,
@@ -187,29 +235,16 @@ impl NameMap {
         &self,
         path: &Path<usize, T>,
     ) -> Option<Path<Vec<u8>, T>> {
-        let mut misses = 0;
-        let new_segs: Vec<u8> = path
-            .iter()
-            .filter_map(|(seg, o)| {
-                let n = self.inverse_map.get(seg).map(|s| (s, o));
-                if n.is_none() {
-                    misses += 1;
-                }
-                n
-            })
-            .enumerate()
-            .flat_map(|(i, (seg, o))| {
-                let s = if i == 0 {
-                    format!("{}{}", seg.as_bstr(), o)
-                } else {
-                    format!(",{}{}", seg.as_bstr(), o)
-                };
-                Vec::from(s.as_bytes())
-            })
-            .collect();
-
-        if misses > 0 {
-            return None;
+        let mut new_segs: Vec<u8> =
+            Vec::with_capacity(path.segment_names.len());
+        let mut separators = path.separators();
+        for (i, (seg, o)) in path.iter().enumerate() {
+            let name = self.inverse_map_name(seg)?;
+            if i != 0 {
+                new_segs.push(separators.next().unwrap_or(b','));
+            }
+            new_segs.extend_from_slice(name);
+            new_segs.extend(format!("{}", o).bytes());
         }
 
         let new_path = Path::new(
@@ -221,6 +256,51 @@ impl NameMap {
         Some(new_path)
     }
 
+    fn map_walk_segments<T: OptFields>(
+        &self,
+        walk: &Walk<Vec<u8>, T>,
+    ) -> Option<Walk<usize, T>> {
+        let mut new_steps: Vec<u8> = Vec::with_capacity(walk.walk.len());
+        for (seg, o) in walk.iter() {
+            let id = self.map_name(seg)?;
+            new_steps.extend(format!("{:#}{}", o, id).bytes());
+        }
+
+        let new_walk = Walk::new(
+            walk.sample_id.clone(),
+            walk.hap_index,
+            walk.seq_id.clone(),
+            walk.seq_start,
+            walk.seq_end,
+            new_steps,
+            walk.optional.clone(),
+        );
+        Some(new_walk)
+    }
+
+    fn inverse_map_walk_segments<T: OptFields>(
+        &self,
+        walk: &Walk<usize, T>,
+    ) -> Option<Walk<Vec<u8>, T>> {
+        let mut new_steps: Vec<u8> = Vec::with_capacity(walk.walk.len());
+        for (seg, o) in walk.iter() {
+            let name = self.inverse_map_name(seg)?;
+            new_steps.extend(format!("{:#}", o).bytes());
+            new_steps.extend_from_slice(name);
+        }
+
+        let new_walk = Walk::new(
+            walk.sample_id.clone(),
+            walk.hap_index,
+            walk.seq_id.clone(),
+            walk.seq_start,
+            walk.seq_end,
+            new_steps,
+            walk.optional.clone(),
+        );
+        Some(new_walk)
+    }
+
     pub fn gfa_bytestring_to_usize<T: OptFields>(
         &self,
         gfa: &GFA<Vec<u8>, T>,
@@ -235,8 +315,17 @@ impl NameMap {
 
         let mut segments = Vec::with_capacity(gfa.segments.len());
         let mut links = Vec::with_capacity(gfa.links.len());
+        let mut jumps = Vec::with_capacity(gfa.jumps.len());
         let mut containments = Vec::with_capacity(gfa.containments.len());
         let mut paths = Vec::with_capacity(gfa.paths.len());
+        let mut walks = Vec::with_capacity(gfa.walks.len());
+        let mut gfa2_segments = Vec::with_capacity(gfa.gfa2_segments.len());
+        let mut edges = Vec::with_capacity(gfa.edges.len());
+        let mut fragments = Vec::with_capacity(gfa.fragments.len());
+        let mut gaps = Vec::with_capacity(gfa.gaps.len());
+        let mut ordered_groups = Vec::with_capacity(gfa.ordered_groups.len());
+        let mut unordered_groups =
+            Vec::with_capacity(gfa.unordered_groups.len());
 
         for seg in gfa.segments.iter() {
             let name = self.map_name(&seg.name)?;
@@ -254,6 +343,15 @@ impl NameMap {
             links.push(new_link);
         }
 
+        for jump in gfa.jumps.iter() {
+            let from_name = self.map_name(&jump.from_segment)?;
+            let to_name = self.map_name(&jump.to_segment)?;
+            let mut new_jump: Jump<usize, T> = jump.nameless_clone();
+            new_jump.from_segment = from_name;
+            new_jump.to_segment = to_name;
+            jumps.push(new_jump);
+        }
+
         for cont in gfa.containments.iter() {
             let container_name = self.map_name(&cont.container_name)?;
             let contained_name = self.map_name(&cont.contained_name)?;
@@ -268,12 +366,80 @@ impl NameMap {
             paths.push(new_path);
         }
 
+        for walk in gfa.walks.iter() {
+            let new_walk = self.map_walk_segments(walk)?;
+            walks.push(new_walk);
+        }
+
+        for seg in gfa.gfa2_segments.iter() {
+            let name = self.map_name(&seg.name)?;
+            let mut new_seg: GFA2Segment<usize, T> = seg.nameless_clone();
+            new_seg.name = name;
+            gfa2_segments.push(new_seg);
+        }
+
+        for edge in gfa.edges.iter() {
+            let sid1 = self.map_name(&edge.sid1)?;
+            let sid2 = self.map_name(&edge.sid2)?;
+            let mut new_edge: Edge<usize, T> = edge.nameless_clone();
+            new_edge.sid1 = sid1;
+            new_edge.sid2 = sid2;
+            edges.push(new_edge);
+        }
+
+        for frag in gfa.fragments.iter() {
+            let segment = self.map_name(&frag.segment)?;
+            let mut new_frag: Fragment<usize, T> = frag.nameless_clone();
+            new_frag.segment = segment;
+            fragments.push(new_frag);
+        }
+
+        for gap in gfa.gaps.iter() {
+            let sid1 = self.map_name(&gap.sid1)?;
+            let sid2 = self.map_name(&gap.sid2)?;
+            let mut new_gap: Gap<usize, T> = gap.nameless_clone();
+            new_gap.sid1 = sid1;
+            new_gap.sid2 = sid2;
+            gaps.push(new_gap);
+        }
+
+        let map_item =
+            |name: &[u8]| Some(self.map_name(name)?.to_string().into_bytes());
+
+        for group in gfa.ordered_groups.iter() {
+            let items = map_group_items(&group.items, true, map_item)?;
+            ordered_groups.push(OrderedGroup::new(
+                group.id.clone(),
+                items,
+                group.optional.clone(),
+            ));
+        }
+
+        for group in gfa.unordered_groups.iter() {
+            let items = map_group_items(&group.items, false, map_item)?;
+            unordered_groups.push(UnorderedGroup::new(
+                group.id.clone(),
+                items,
+                group.optional.clone(),
+            ));
+        }
+
         Some(GFA {
             header: gfa.header.clone(),
             segments,
             links,
+            jumps,
             containments,
             paths,
+            walks,
+            gfa2_segments,
+            edges,
+            fragments,
+            gaps,
+            ordered_groups,
+            unordered_groups,
+            raw_lines: gfa.raw_lines.clone(),
+            line_order: gfa.line_order.clone(),
         })
     }
 
@@ -283,8 +449,17 @@ impl NameMap {
     ) -> Option<GFA<Vec<u8>, T>> {
         let mut segments = Vec::with_capacity(gfa.segments.len());
         let mut links = Vec::with_capacity(gfa.links.len());
+        let mut jumps = Vec::with_capacity(gfa.jumps.len());
         let mut containments = Vec::with_capacity(gfa.containments.len());
         let mut paths = Vec::with_capacity(gfa.paths.len());
+        let mut walks = Vec::with_capacity(gfa.walks.len());
+        let mut gfa2_segments = Vec::with_capacity(gfa.gfa2_segments.len());
+        let mut edges = Vec::with_capacity(gfa.edges.len());
+        let mut fragments = Vec::with_capacity(gfa.fragments.len());
+        let mut gaps = Vec::with_capacity(gfa.gaps.len());
+        let mut ordered_groups = Vec::with_capacity(gfa.ordered_groups.len());
+        let mut unordered_groups =
+            Vec::with_capacity(gfa.unordered_groups.len());
 
         for seg in gfa.segments.iter() {
             let name = self.inverse_map_name(seg.name)?;
@@ -303,6 +478,15 @@ impl NameMap {
             links.push(new_link);
         }
 
+        for jump in gfa.jumps.iter() {
+            let from_name = self.inverse_map_name(jump.from_segment)?;
+            let to_name = self.inverse_map_name(jump.to_segment)?;
+            let mut new_jump: Jump<Vec<u8>, T> = jump.nameless_clone();
+            new_jump.from_segment = Vec::from_slice(from_name);
+            new_jump.to_segment = Vec::from_slice(to_name);
+            jumps.push(new_jump);
+        }
+
         for cont in gfa.containments.iter() {
             let container_name = self.inverse_map_name(cont.container_name)?;
             let contained_name = self.inverse_map_name(cont.contained_name)?;
@@ -317,12 +501,82 @@ impl NameMap {
             paths.push(new_path);
         }
 
+        for walk in gfa.walks.iter() {
+            let new_walk = self.inverse_map_walk_segments(walk)?;
+            walks.push(new_walk);
+        }
+
+        for seg in gfa.gfa2_segments.iter() {
+            let name = self.inverse_map_name(seg.name)?;
+            let mut new_seg: GFA2Segment<Vec<u8>, T> = seg.nameless_clone();
+            new_seg.name = Vec::from_slice(name);
+            gfa2_segments.push(new_seg);
+        }
+
+        for edge in gfa.edges.iter() {
+            let sid1 = self.inverse_map_name(edge.sid1)?;
+            let sid2 = self.inverse_map_name(edge.sid2)?;
+            let mut new_edge: Edge<Vec<u8>, T> = edge.nameless_clone();
+            new_edge.sid1 = Vec::from_slice(sid1);
+            new_edge.sid2 = Vec::from_slice(sid2);
+            edges.push(new_edge);
+        }
+
+        for frag in gfa.fragments.iter() {
+            let segment = self.inverse_map_name(frag.segment)?;
+            let mut new_frag: Fragment<Vec<u8>, T> = frag.nameless_clone();
+            new_frag.segment = Vec::from_slice(segment);
+            fragments.push(new_frag);
+        }
+
+        for gap in gfa.gaps.iter() {
+            let sid1 = self.inverse_map_name(gap.sid1)?;
+            let sid2 = self.inverse_map_name(gap.sid2)?;
+            let mut new_gap: Gap<Vec<u8>, T> = gap.nameless_clone();
+            new_gap.sid1 = Vec::from_slice(sid1);
+            new_gap.sid2 = Vec::from_slice(sid2);
+            gaps.push(new_gap);
+        }
+
+        let map_item = |name: &[u8]| {
+            let id = usize::parse_id(name)?;
+            self.inverse_map_name(id).map(Vec::from_slice)
+        };
+
+        for group in gfa.ordered_groups.iter() {
+            let items = map_group_items(&group.items, true, map_item)?;
+            ordered_groups.push(OrderedGroup::new(
+                group.id.clone(),
+                items,
+                group.optional.clone(),
+            ));
+        }
+
+        for group in gfa.unordered_groups.iter() {
+            let items = map_group_items(&group.items, false, map_item)?;
+            unordered_groups.push(UnorderedGroup::new(
+                group.id.clone(),
+                items,
+                group.optional.clone(),
+            ));
+        }
+
         Some(GFA {
             header: gfa.header.clone(),
             segments,
             links,
+            jumps,
             containments,
             paths,
+            walks,
+            gfa2_segments,
+            edges,
+            fragments,
+            gaps,
+            ordered_groups,
+            unordered_groups,
+            raw_lines: gfa.raw_lines.clone(),
+            line_order: gfa.line_order.clone(),
         })
     }
 
@@ -355,6 +609,32 @@ impl NameMap {
             get_ix(cont.container_name.as_ref());
             get_ix(cont.contained_name.as_ref());
         }
+        for seg in gfa.gfa2_segments.iter() {
+            get_ix(seg.name.as_ref());
+        }
+        for edge in gfa.edges.iter() {
+            get_ix(edge.sid1.as_ref());
+            get_ix(edge.sid2.as_ref());
+        }
+        for frag in gfa.fragments.iter() {
+            get_ix(frag.segment.as_ref());
+        }
+        for gap in gfa.gaps.iter() {
+            get_ix(gap.sid1.as_ref());
+            get_ix(gap.sid2.as_ref());
+        }
+        // Group items can also refer to edges and other groups, so
+        // every referenced name is added to the map
+        for group in gfa.ordered_groups.iter() {
+            for (name, _) in group.iter() {
+                get_ix(name);
+            }
+        }
+        for group in gfa.unordered_groups.iter() {
+            for name in group.iter() {
+                get_ix(name);
+            }
+        }
 
         NameMap {
             name_map,
@@ -369,10 +649,12 @@ mod tests {
     use super::*;
     use crate::parser::GFAParser;
 
+    #[cfg(feature = "serde1")]
     fn diatom_name_map_path() -> &'static str {
         "./test/gfas/diatom_map.json"
     }
 
+    #[cfg(feature = "serde1")]
     fn lil_name_map_path() -> &'static str {
         "./test/gfas/lil_map.json"
     }
@@ -380,14 +662,14 @@ mod tests {
     fn load_diatom_gfa() -> GFA<Vec<u8>, OptionalFields> {
         let parser = GFAParser::new();
         let gfa: GFA<Vec<u8>, OptionalFields> =
-            parser.parse_file(&"./test/gfas/diatom.gfa").unwrap();
+            parser.parse_file("./test/gfas/diatom.gfa").unwrap();
         gfa
     }
 
     fn load_lil_gfa() -> GFA<Vec<u8>, OptionalFields> {
         let parser = GFAParser::new();
         let gfa: GFA<Vec<u8>, OptionalFields> =
-            parser.parse_file(&"./test/gfas/lil.gfa").unwrap();
+            parser.parse_file("./test/gfas/lil.gfa").unwrap();
         gfa
     }
 
@@ -395,7 +677,7 @@ mod tests {
         let name_map = NameMap::build_from_gfa(original_gfa);
 
         let usize_gfa = name_map
-            .gfa_bytestring_to_usize(&original_gfa, false)
+            .gfa_bytestring_to_usize(original_gfa, false)
             .unwrap();
 
         assert_eq!(original_gfa.segments.len(), usize_gfa.segments.len());
@@ -405,6 +687,21 @@ mod tests {
             usize_gfa.containments.len()
         );
         assert_eq!(original_gfa.paths.len(), usize_gfa.paths.len());
+        assert_eq!(
+            original_gfa.gfa2_segments.len(),
+            usize_gfa.gfa2_segments.len()
+        );
+        assert_eq!(original_gfa.edges.len(), usize_gfa.edges.len());
+        assert_eq!(original_gfa.fragments.len(), usize_gfa.fragments.len());
+        assert_eq!(original_gfa.gaps.len(), usize_gfa.gaps.len());
+        assert_eq!(
+            original_gfa.ordered_groups.len(),
+            usize_gfa.ordered_groups.len()
+        );
+        assert_eq!(
+            original_gfa.unordered_groups.len(),
+            usize_gfa.unordered_groups.len()
+        );
 
         let inverted_gfa =
             name_map.gfa_usize_to_bytestring(&usize_gfa).unwrap();
@@ -418,6 +715,36 @@ mod tests {
         test_isomorphism(&original_gfa);
     }
 
+    #[test]
+    fn jumps_name_map_isomorphism() {
+        let parser = GFAParser::new();
+        let original_gfa: GFA<Vec<u8>, OptionalFields> =
+            parser.parse_file("./test/gfas/jumps.gfa").unwrap();
+        assert_eq!(original_gfa.jumps.len(), 2);
+        test_isomorphism(&original_gfa);
+    }
+
+    #[test]
+    fn walks_name_map_isomorphism() {
+        let parser = GFAParser::new();
+        let original_gfa: GFA<Vec<u8>, OptionalFields> =
+            parser.parse_file("./test/gfas/walks.gfa").unwrap();
+        assert_eq!(original_gfa.walks.len(), 2);
+        test_isomorphism(&original_gfa);
+    }
+
+    #[test]
+    fn gfa2_name_map_isomorphism() {
+        let parser = GFAParser::new();
+        let original_gfa: GFA<Vec<u8>, OptionalFields> =
+            parser.parse_file("./test/gfas/gfa2.gfa").unwrap();
+        assert_eq!(original_gfa.gfa2_segments.len(), 5);
+        assert_eq!(original_gfa.edges.len(), 3);
+        assert_eq!(original_gfa.ordered_groups.len(), 1);
+        assert_eq!(original_gfa.unordered_groups.len(), 1);
+        test_isomorphism(&original_gfa);
+    }
+
     #[test]
     fn diatom_name_map_isomorphism() {
         let original_gfa = load_diatom_gfa();
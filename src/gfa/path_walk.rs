@@ -0,0 +1,353 @@
+//! Conversion between paths (P-lines) and walks (W-lines).
+//!
+//! Paths are matched to walks using their names, which must follow
+//! the PanSN convention `sample#haplotype#contig`, optionally
+//! followed by a `[start-end]` range suffix that holds the walk
+//! coordinates. A path without a range suffix corresponds to a walk
+//! with `*` coordinates. The coordinates are checked against the
+//! lengths of the segments that are visited.
+//!
+//! The conversion is lossless: converting a path to a walk and back,
+//! or a walk to a path and back, gives the original line. Lines that
+//! can't be converted without losing information, such as paths with
+//! overlaps, aren't converted at all.
+
+use crate::{
+    gfa::{
+        is_step_separator, walk_steps, Orientation, Path, Segment, SegmentId,
+        Walk, GFA,
+    },
+    optfields::OptFields,
+};
+
+use bstr::ByteSlice;
+use fnv::FnvHashMap;
+
+use std::{convert::TryFrom, hash::Hash};
+
+/// The lengths of the segments in a GFA, used to compute walk
+/// coordinates. Segments without a sequence (i.e. `*`) use their
+/// `LN` tag, if present.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentLengths<N: Hash + Eq> {
+    lengths: FnvHashMap<N, usize>,
+}
+
+fn segment_length<N, T: OptFields>(seg: &Segment<N, T>) -> Option<usize> {
+    if seg.sequence != b"*" {
+        return Some(seg.sequence.len());
+    }
+    let len = seg.optional.get_field(b"LN")?.get_int()?;
+    usize::try_from(len).ok()
+}
+
+/// A PanSN name split into its parts, with the `[start-end]` range
+/// suffix, if any, split from the contig name.
+struct PanSNName<'a> {
+    sample: &'a [u8],
+    hap_index: usize,
+    contig: &'a [u8],
+    range: Option<(usize, usize)>,
+}
+
+fn parse_pansn_name(name: &[u8]) -> Option<PanSNName<'_>> {
+    let mut parts = name.splitn(3, |&b| b == b'#');
+    let sample = parts.next()?;
+    let hap_index = parts.next()?.to_str().ok()?.parse().ok()?;
+    let contig = parts.next()?;
+
+    let range_start = contig
+        .last()
+        .filter(|&&b| b == b']')
+        .and_then(|_| contig.rfind_byte(b'['));
+
+    let (contig, range) = match range_start {
+        Some(ix) => {
+            let range = &contig[ix + 1..contig.len() - 1];
+            let dash = range.find_byte(b'-')?;
+            let start = range[..dash].to_str().ok()?.parse().ok()?;
+            let end = range[dash + 1..].to_str().ok()?.parse().ok()?;
+            (&contig[..ix], Some((start, end)))
+        }
+        None => (contig, None),
+    };
+
+    Some(PanSNName {
+        sample,
+        hap_index,
+        contig,
+        range,
+    })
+}
+
+/// Splits a path step such as `s1+` into the name and orientation.
+fn split_path_step(step: &[u8]) -> Option<(&[u8], Orientation)> {
+    let (last, name) = step.split_last()?;
+    let orient = Orientation::from_bytes_plus_minus([*last])?;
+    Some((name, orient))
+}
+
+impl<N: SegmentId + Clone + Hash + Eq> SegmentLengths<N> {
+    /// Collect the lengths of all GFA1 and GFA2 segments in the GFA.
+    pub fn from_gfa<T: OptFields>(gfa: &GFA<N, T>) -> Self {
+        let mut lengths = FnvHashMap::default();
+        for seg in gfa.segments.iter() {
+            if let Some(len) = segment_length(seg) {
+                lengths.insert(seg.name.clone(), len);
+            }
+        }
+        for seg in gfa.gfa2_segments.iter() {
+            lengths.insert(seg.name.clone(), seg.length);
+        }
+        SegmentLengths { lengths }
+    }
+
+    pub fn get(&self, segment: &N) -> Option<usize> {
+        self.lengths.get(segment).copied()
+    }
+
+    fn step_length(&self, name: &[u8]) -> Option<usize> {
+        let id = N::parse_id(name)?;
+        self.get(&id)
+    }
+
+    /// Convert a path with a PanSN name to a walk, taking the walk
+    /// coordinates from the range suffix of the name, or `*` if there
+    /// is none. Returns `None` if the name isn't in PanSN format, if
+    /// a step is missing from the segment lengths, if the length of
+    /// the path doesn't match its range suffix, or if the path has
+    /// jumps or overlaps other than a single `*`, which walks can't
+    /// represent.
+    pub fn path_to_walk<T: OptFields>(
+        &self,
+        path: &Path<N, T>,
+    ) -> Option<Walk<N, T>> {
+        let pansn = parse_pansn_name(&path.path_name)?;
+
+        if path.separators().any(|sep| sep == b';') {
+            return None;
+        }
+        if !matches!(path.overlaps[..], [None]) {
+            return None;
+        }
+
+        let mut steps = Vec::with_capacity(path.segment_names.len());
+        let mut length = 0;
+        for step in path.segment_names.split(|&b| is_step_separator(b)) {
+            let (name, orient) = split_path_step(step)?;
+            length = self.step_length(name)?.checked_add(length)?;
+            steps.extend(format!("{:#}", orient).bytes());
+            steps.extend_from_slice(name);
+        }
+
+        if let Some((start, end)) = pansn.range {
+            if start.checked_add(length)? != end {
+                return None;
+            }
+        }
+
+        Some(Walk::new(
+            pansn.sample.into(),
+            pansn.hap_index,
+            pansn.contig.into(),
+            pansn.range.map(|(start, _)| start),
+            pansn.range.map(|(_, end)| end),
+            steps,
+            path.optional.clone(),
+        ))
+    }
+
+    /// Convert a walk to a path with a PanSN name, with the walk
+    /// coordinates as a range suffix of the name, unless they're `*`.
+    /// The path's overlaps are `*`. Returns `None` if a step is
+    /// missing from the segment lengths, if the walk's coordinates
+    /// don't match the segment lengths, or if only one of them is
+    /// `*`.
+    pub fn walk_to_path<T: OptFields>(
+        &self,
+        walk: &Walk<N, T>,
+    ) -> Option<Path<N, T>> {
+        let mut steps = Vec::with_capacity(walk.walk.len());
+        let mut length = 0;
+        for (i, (name, orient)) in walk_steps(&walk.walk).enumerate() {
+            length = self.step_length(name)?.checked_add(length)?;
+            if i != 0 {
+                steps.push(b',');
+            }
+            steps.extend_from_slice(name);
+            steps.extend(format!("{}", orient).bytes());
+        }
+
+        let range = match (walk.seq_start, walk.seq_end) {
+            (Some(start), Some(end)) if start.checked_add(length)? == end => {
+                Some((start, end))
+            }
+            (None, None) => None,
+            _ => return None,
+        };
+
+        let mut path_name = walk.sample_id.clone();
+        path_name.extend(format!("#{}#", walk.hap_index).bytes());
+        path_name.extend_from_slice(&walk.seq_id);
+        if let Some((start, end)) = range {
+            path_name.extend(format!("[{}-{}]", start, end).bytes());
+        }
+
+        Some(Path::new(
+            path_name,
+            steps,
+            vec![None],
+            walk.optional.clone(),
+        ))
+    }
+}
+
+impl<N: SegmentId + Clone + Hash + Eq, T: OptFields> GFA<N, T> {
+    /// Convert every path in the GFA to a walk, using the lengths of
+    /// the segments in the GFA. Returns `None` if any of the paths
+    /// can't be converted; see `SegmentLengths::path_to_walk`.
+    pub fn paths_to_walks(&self) -> Option<Vec<Walk<N, T>>> {
+        let lengths = SegmentLengths::from_gfa(self);
+        self.paths
+            .iter()
+            .map(|path| lengths.path_to_walk(path))
+            .collect()
+    }
+
+    /// Convert every walk in the GFA to a path, using the lengths of
+    /// the segments in the GFA. Returns `None` if any of the walks
+    /// can't be converted; see `SegmentLengths::walk_to_path`.
+    pub fn walks_to_paths(&self) -> Option<Vec<Path<N, T>>> {
+        let lengths = SegmentLengths::from_gfa(self);
+        self.walks
+            .iter()
+            .map(|walk| lengths.walk_to_path(walk))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cigar::CIGAR, optfields::OptionalFields, parser::GFAParser};
+
+    fn load_walks_gfa<N: SegmentId>() -> GFA<N, OptionalFields> {
+        let parser = GFAParser::new();
+        parser.parse_file("./test/gfas/walks.gfa").unwrap()
+    }
+
+    #[test]
+    fn walk_path_roundtrip() {
+        let gfa: GFA<Vec<u8>, OptionalFields> = load_walks_gfa();
+        let paths = gfa.walks_to_paths().unwrap();
+
+        assert_eq!(paths[0].path_name, b"HG002#1#chr1[0-18]");
+        assert_eq!(paths[0].segment_names, b"11+,12-,13+");
+        // The second walk has `*` coordinates, so there's no range
+        assert_eq!(paths[1].path_name, b"HG002#2#chr1");
+
+        // W -> P -> W gives the original walks
+        let lengths = SegmentLengths::from_gfa(&gfa);
+        for (path, walk) in paths.iter().zip(gfa.walks.iter()) {
+            assert_eq!(&lengths.path_to_walk(path).unwrap(), walk);
+        }
+
+        // P -> W -> P gives the original paths
+        let input: &[u8] = b"S\t11\tACCTT
+S\t12\tTCAAGG
+P\tHG002#1#chr1\t11+,12-\t*\tSN:Z:chr1
+P\tHG002#2#chr1[0-11]\t12+,11-\t*
+P\tHG002#3#chr1[7-12]\t11+\t*
+";
+        let parser = GFAParser::new();
+        let gfa: GFA<Vec<u8>, OptionalFields> =
+            parser.parse_lines(input.lines()).unwrap();
+        let walks = gfa.paths_to_walks().unwrap();
+        assert_eq!((walks[0].seq_start, walks[0].seq_end), (None, None));
+        assert_eq!((walks[1].seq_start, walks[1].seq_end), (Some(0), Some(11)));
+        let lengths = SegmentLengths::from_gfa(&gfa);
+        for (walk, path) in walks.iter().zip(gfa.paths.iter()) {
+            assert_eq!(&lengths.walk_to_path(walk).unwrap(), path);
+        }
+    }
+
+    #[test]
+    fn path_walk_roundtrip_usize() {
+        let mut gfa: GFA<usize, OptionalFields> = load_walks_gfa();
+        let path = Path::new(
+            "HG003#0#chr2[100-112]".into(),
+            "13-,11+".into(),
+            vec![None],
+            Vec::new(),
+        );
+        gfa.paths.push(path);
+
+        let walks = gfa.paths_to_walks().unwrap();
+        assert_eq!(walks[0].sample_id, b"HG003");
+        assert_eq!(walks[0].hap_index, 0);
+        assert_eq!(walks[0].seq_id, b"chr2");
+        assert_eq!(walks[0].seq_start, Some(100));
+        assert_eq!(walks[0].seq_end, Some(112));
+        assert_eq!(walks[0].walk, b"<13>11");
+
+        let lengths = SegmentLengths::from_gfa(&gfa);
+        let path = lengths.walk_to_path(&walks[0]).unwrap();
+        assert_eq!(path, gfa.paths[0]);
+    }
+
+    #[test]
+    fn unconvertible_paths() {
+        let gfa: GFA<Vec<u8>, OptionalFields> = load_walks_gfa();
+        let lengths = SegmentLengths::from_gfa(&gfa);
+
+        let not_pansn: Path<Vec<u8>, OptionalFields> =
+            Path::new("chr1".into(), "11+".into(), vec![None], Vec::new());
+        assert!(lengths.path_to_walk(&not_pansn).is_none());
+
+        let bad_range: Path<Vec<u8>, OptionalFields> = Path::new(
+            "HG002#1#chr1[0-100]".into(),
+            "11+".into(),
+            vec![None],
+            Vec::new(),
+        );
+        assert!(lengths.path_to_walk(&bad_range).is_none());
+
+        let missing_seg: Path<Vec<u8>, OptionalFields> = Path::new(
+            "HG002#1#chr1".into(),
+            "11+,99-".into(),
+            vec![None],
+            Vec::new(),
+        );
+        assert!(lengths.path_to_walk(&missing_seg).is_none());
+
+        // Walks have no overlaps, so paths with overlaps other than
+        // `*` can't be converted without losing them
+        let overlaps: Path<Vec<u8>, OptionalFields> = Path::new(
+            "HG002#1#chr1".into(),
+            "11+,13+".into(),
+            vec![CIGAR::from_bytestring(b"0M")],
+            Vec::new(),
+        );
+        assert!(lengths.path_to_walk(&overlaps).is_none());
+
+        // Only one of the walk coordinates is `*`
+        let mut walk = gfa.walks[0].clone();
+        walk.seq_end = None;
+        assert!(lengths.walk_to_path(&walk).is_none());
+
+        // Negative and overflowing lengths can't be converted
+        let parser = GFAParser::new();
+        let input: &[u8] = b"S\ts1\t*\tLN:i:-1\nP\tA#0#c\ts1+\t*\n";
+        let gfa: GFA<Vec<u8>, OptionalFields> =
+            parser.parse_lines(input.lines()).unwrap();
+        assert!(gfa.paths_to_walks().is_none());
+
+        let huge: Path<Vec<u8>, OptionalFields> = Path::new(
+            format!("HG002#1#chr1[{}-{}]", usize::MAX, usize::MAX).into(),
+            "11+".into(),
+            vec![None],
+            Vec::new(),
+        );
+        assert!(lengths.path_to_walk(&huge).is_none());
+    }
+}
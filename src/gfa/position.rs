@@ -0,0 +1,73 @@
+#[cfg(feature = "serde1")]
+use serde::{Deserialize, Serialize};
+
+use bstr::ByteSlice;
+
+/// A position on a segment or fragment, as used in GFA2 edge and
+/// fragment lines. A position suffixed with `$` marks the end of the
+/// sequence it refers to, which is stored in `is_end`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Position {
+    pub pos: usize,
+    pub is_end: bool,
+}
+
+impl Position {
+    #[inline]
+    pub fn new(pos: usize, is_end: bool) -> Self {
+        Position { pos, is_end }
+    }
+
+    /// Parse a position from a bytestring in the format `123` or
+    /// `123$`.
+    #[inline]
+    pub fn parse<T: AsRef<[u8]>>(bs: T) -> Option<Self> {
+        let bs = bs.as_ref();
+        let (digits, is_end) = match bs.split_last()? {
+            (b'$', rest) => (rest, true),
+            _ => (bs, false),
+        };
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let pos = digits.to_str().ok()?.parse().ok()?;
+        Some(Position { pos, is_end })
+    }
+}
+
+/// Display produces the GFA2 representation, appending `$` if the
+/// position is at the end of its sequence.
+///
+/// # Examples
+///
+/// ```
+/// use gfa::gfa::Position;
+///
+/// assert_eq!(&format!("{}", Position::new(12, false)), "12");
+/// assert_eq!(&format!("{}", Position::new(140, true)), "140$");
+/// ```
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_end {
+            write!(f, "{}$", self.pos)
+        } else {
+            write!(f, "{}", self.pos)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_positions() {
+        assert_eq!(Position::parse(b"0"), Some(Position::new(0, false)));
+        assert_eq!(Position::parse(b"123$"), Some(Position::new(123, true)));
+        assert_eq!(Position::parse(b"$"), None);
+        assert_eq!(Position::parse(b"-5"), None);
+        assert_eq!(Position::parse(b"12$3"), None);
+        assert_eq!(Position::parse(b""), None);
+    }
+}
@@ -1,8 +1,6 @@
-use crate::parser::ParseFieldError;
+use crate::parser::{scan, ParseFieldError};
 
 use bstr::ByteSlice;
-use lazy_static::lazy_static;
-use regex::bytes::Regex;
 
 /// Trait for the types that can be parsed and used as segment IDs;
 /// will probably only be usize and Vec<u8>.
@@ -22,6 +20,16 @@ pub trait SegmentId: Sized + Default {
     }
 
     fn display(&self) -> String;
+
+    /// Write the ID as it appears in a GFA file. Unlike `display`,
+    /// bytes that aren't valid UTF-8 are written unchanged.
+    #[inline]
+    fn write_id<W: std::io::Write>(
+        &self,
+        stream: &mut W,
+    ) -> std::io::Result<()> {
+        stream.write_all(self.display().as_bytes())
+    }
 }
 
 impl SegmentId for usize {
@@ -43,15 +51,19 @@ impl SegmentId for Vec<u8> {
 
     #[inline]
     fn parse_id(input: &[u8]) -> Option<Self> {
-        lazy_static! {
-            static ref RE: Regex =
-                Regex::new(r"(?-u)[!-)+-<>-~][!-~]*").unwrap();
-        }
-        RE.find(input).map(|s| Vec::from(s.as_bytes()))
+        scan::find_segment_name(input).map(Vec::from)
     }
 
     #[inline]
     fn display(&self) -> String {
         self.as_bstr().to_string()
     }
+
+    #[inline]
+    fn write_id<W: std::io::Write>(
+        &self,
+        stream: &mut W,
+    ) -> std::io::Result<()> {
+        stream.write_all(self)
+    }
 }
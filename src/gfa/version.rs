@@ -0,0 +1,126 @@
+#[cfg(feature = "serde1")]
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+use crate::gfa::LineKind;
+
+/// The versions of the GFA format. Each GFA1 version adds line types
+/// to the previous one, while GFA2 has a mostly separate set of line
+/// types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub enum GFAVersion {
+    V1_0,
+    /// Adds walks.
+    V1_1,
+    /// Adds jumps, and jumps between the steps of paths.
+    V1_2,
+    V2_0,
+}
+
+impl GFAVersion {
+    /// Parse a version number, as found in the `VN` tag of a header.
+    /// Major versions without a minor version are also accepted.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            b"1.0" | b"1" => Some(GFAVersion::V1_0),
+            b"1.1" => Some(GFAVersion::V1_1),
+            b"1.2" => Some(GFAVersion::V1_2),
+            b"2.0" | b"2" => Some(GFAVersion::V2_0),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GFAVersion::V1_0 => "1.0",
+            GFAVersion::V1_1 => "1.1",
+            GFAVersion::V1_2 => "1.2",
+            GFAVersion::V2_0 => "2.0",
+        }
+    }
+
+    #[inline]
+    pub fn is_gfa2(&self) -> bool {
+        *self == GFAVersion::V2_0
+    }
+
+    /// The first version that includes lines of the given kind, or
+    /// `None` for headers and raw lines, which are in every version.
+    pub fn introducing(kind: LineKind) -> Option<Self> {
+        use LineKind as K;
+        match kind {
+            K::Header | K::Raw => None,
+            K::Segment | K::Link | K::Containment | K::Path => {
+                Some(GFAVersion::V1_0)
+            }
+            K::Walk => Some(GFAVersion::V1_1),
+            K::Jump => Some(GFAVersion::V1_2),
+            K::GFA2Segment
+            | K::Edge
+            | K::Fragment
+            | K::Gap
+            | K::OrderedGroup
+            | K::UnorderedGroup => Some(GFAVersion::V2_0),
+        }
+    }
+
+    /// Returns true if lines of the given kind are allowed in this
+    /// version.
+    pub fn allows(&self, kind: LineKind) -> bool {
+        match GFAVersion::introducing(kind) {
+            Some(version) => {
+                version.is_gfa2() == self.is_gfa2() && version <= *self
+            }
+            None => true,
+        }
+    }
+
+    /// Detect the version from the kinds of lines in a GFA, as the
+    /// first version that includes all of them. Mixing GFA1 and GFA2
+    /// lines gives GFA2, and a GFA without any lines that decide the
+    /// version is GFA 1.0.
+    pub fn detect<I>(kinds: I) -> Self
+    where
+        I: IntoIterator<Item = LineKind>,
+    {
+        kinds
+            .into_iter()
+            .filter_map(GFAVersion::introducing)
+            .max()
+            .unwrap_or(GFAVersion::V1_0)
+    }
+}
+
+impl fmt::Display for GFAVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_line_kinds() {
+        use GFAVersion::*;
+
+        assert_eq!(GFAVersion::from_bytes(b"1.1"), Some(V1_1));
+        assert_eq!(GFAVersion::from_bytes(b"2"), Some(V2_0));
+        assert_eq!(GFAVersion::from_bytes(b"1.3"), None);
+
+        assert!(V1_2.allows(LineKind::Walk));
+        assert!(!V1_0.allows(LineKind::Walk));
+        assert!(!V1_2.allows(LineKind::Edge));
+        assert!(!V2_0.allows(LineKind::Link));
+        assert!(V2_0.allows(LineKind::Header));
+
+        let detect = |kinds: &[LineKind]| GFAVersion::detect(kinds.to_vec());
+        assert_eq!(detect(&[]), V1_0);
+        assert_eq!(detect(&[LineKind::Segment, LineKind::Jump]), V1_2);
+        assert_eq!(detect(&[LineKind::Walk, LineKind::Path]), V1_1);
+        assert_eq!(detect(&[LineKind::GFA2Segment, LineKind::Edge]), V2_0);
+    }
+}
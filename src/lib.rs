@@ -1,4 +1,5 @@
 pub mod cigar;
+pub mod compression;
 pub mod gafpaf;
 pub mod gfa;
 pub mod mmap;
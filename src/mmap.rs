@@ -1,5 +1,6 @@
 use crate::{
-    gfa::{Line, Link, Path, Segment},
+    compression::{is_bgzf, is_gzip, BgzfReader},
+    gfa::{borrowed::BorrowedLine, Line, Link, Path, Segment},
     parser::GFAParser,
 };
 
@@ -7,20 +8,93 @@ use anyhow::{bail, Result};
 
 use memmap::Mmap;
 
+use flate2::bufread::MultiGzDecoder;
+
 use std::fs::File;
-use std::io::prelude::*;
+use std::io::{self, prelude::*, Cursor};
 
 use bstr::ByteSlice;
 
+/// Memory-mapped GFA file, which can also be gzip or BGZF compressed.
+///
+/// For BGZF files, the positions and offsets used by `MmapGFA`, such
+/// as those in the `LineIndices` produced by `build_index`, are BGZF
+/// virtual offsets, which can be used to seek to a line without
+/// decompressing the entire file. Other gzip files don't support
+/// random access, and are instead decompressed into memory when
+/// opened.
 #[derive(Debug)]
 pub struct MmapGFA {
-    pub cursor: std::io::Cursor<Mmap>,
+    cursor: MmapCursor,
     pub line_buf: Vec<u8>,
     pub current_line_len: usize,
     pub last_buf_offset: usize,
     pub parser: GFAParser<usize, ()>,
 }
 
+#[derive(Debug)]
+enum MmapCursor {
+    Plain(Cursor<Mmap>),
+    Decompressed(Cursor<Vec<u8>>),
+    // Seeking a BGZF reader can fail, so a new position is stored
+    // until the next read, where any error can be returned
+    Bgzf(BgzfReader<Cursor<Mmap>>, Option<u64>),
+}
+
+impl MmapCursor {
+    fn new(mmap: Mmap) -> io::Result<Self> {
+        if is_bgzf(&mmap) {
+            let reader = BgzfReader::new(Cursor::new(mmap));
+            Ok(MmapCursor::Bgzf(reader, None))
+        } else if is_gzip(&mmap) {
+            let mut bytes = Vec::new();
+            MultiGzDecoder::new(&mmap[..]).read_to_end(&mut bytes)?;
+            Ok(MmapCursor::Decompressed(Cursor::new(bytes)))
+        } else {
+            Ok(MmapCursor::Plain(Cursor::new(mmap)))
+        }
+    }
+
+    fn position(&self) -> u64 {
+        match self {
+            MmapCursor::Plain(cursor) => cursor.position(),
+            MmapCursor::Decompressed(cursor) => cursor.position(),
+            MmapCursor::Bgzf(reader, pending) => {
+                pending.unwrap_or_else(|| reader.virtual_offset())
+            }
+        }
+    }
+
+    fn set_position(&mut self, pos: u64) {
+        match self {
+            MmapCursor::Plain(cursor) => cursor.set_position(pos),
+            MmapCursor::Decompressed(cursor) => cursor.set_position(pos),
+            MmapCursor::Bgzf(_, pending) => *pending = Some(pos),
+        }
+    }
+
+    fn get_ref(&self) -> &[u8] {
+        match self {
+            MmapCursor::Plain(cursor) => cursor.get_ref(),
+            MmapCursor::Decompressed(cursor) => cursor.get_ref(),
+            MmapCursor::Bgzf(reader, _) => reader.get_ref().get_ref(),
+        }
+    }
+
+    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        match self {
+            MmapCursor::Plain(cursor) => cursor.read_until(byte, buf),
+            MmapCursor::Decompressed(cursor) => cursor.read_until(byte, buf),
+            MmapCursor::Bgzf(reader, pending) => {
+                if let Some(pos) = pending.take() {
+                    reader.seek_virtual(pos)?;
+                }
+                reader.read_until(byte, buf)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineType {
     Segment,
@@ -48,9 +122,7 @@ impl<'a> Iterator for SegmentIter<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         while let Ok(line) = self.mmap.next_line() {
             if let Some(b'S') = line.first() {
-                if let Some(Line::Segment(s)) =
-                    self.parser.parse_gfa_line(line).ok()
-                {
+                if let Ok(Line::Segment(s)) = self.parser.parse_gfa_line(line) {
                     return Some(s);
                 }
             }
@@ -72,9 +144,7 @@ impl<'a> Iterator for LinkIter<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         while let Ok(line) = self.mmap.next_line() {
             if let Some(b'S') = line.first() {
-                if let Some(Line::Link(s)) =
-                    self.parser.parse_gfa_line(line).ok()
-                {
+                if let Ok(Line::Link(s)) = self.parser.parse_gfa_line(line) {
                     return Some(s);
                 }
             }
@@ -96,9 +166,7 @@ impl<'a> Iterator for PathIter<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         while let Ok(line) = self.mmap.next_line() {
             if let Some(b'S') = line.first() {
-                if let Some(Line::Path(s)) =
-                    self.parser.parse_gfa_line(line).ok()
-                {
+                if let Ok(Line::Path(s)) = self.parser.parse_gfa_line(line) {
                     return Some(s);
                 }
             }
@@ -112,7 +180,7 @@ impl MmapGFA {
         let file = File::open(path)?;
         let mmap = unsafe { Mmap::map(&file)? };
 
-        let cursor = std::io::Cursor::new(mmap);
+        let cursor = MmapCursor::new(mmap)?;
         let line_buf = Vec::with_capacity(1024);
         let current_line_len = 0;
         let last_buf_offset = 0;
@@ -139,8 +207,37 @@ impl MmapGFA {
         self.cursor.set_position(new_pos);
         cur_pos
     }
+
+    /// The position the next line is read from, which is a BGZF
+    /// virtual offset if the file is BGZF compressed.
+    pub fn position(&self) -> u64 {
+        self.cursor.position()
+    }
+
+    /// The bytes of the GFA file, i.e. the memory-mapped file, or the
+    /// decompressed bytes if the file is gzip compressed. If the file
+    /// is BGZF compressed, these are the compressed bytes.
     pub fn get_ref(&self) -> &[u8] {
-        self.cursor.get_ref().as_ref()
+        self.cursor.get_ref()
+    }
+
+    /// The cursor over the memory-mapped file, if the file isn't
+    /// compressed.
+    pub fn cursor(&self) -> Option<&Cursor<Mmap>> {
+        match &self.cursor {
+            MmapCursor::Plain(cursor) => Some(cursor),
+            _ => None,
+        }
+    }
+
+    /// The cursor over the memory-mapped file, if the file isn't
+    /// compressed. Moving the cursor moves the position the next line
+    /// is read from.
+    pub fn cursor_mut(&mut self) -> Option<&mut Cursor<Mmap>> {
+        match &mut self.cursor {
+            MmapCursor::Plain(cursor) => Some(cursor),
+            _ => None,
+        }
     }
 
     pub fn get_parser(&self) -> &GFAParser<usize, ()> {
@@ -175,13 +272,16 @@ impl MmapGFA {
 
         self.cursor.set_position(0);
 
-        let mut line_start = 0;
-
         loop {
             let line = self.next_line()?;
             let length = line.len();
+            let first = line.first().copied();
 
-            if let Some(ref byte) = line.first() {
+            // Offsets can't be computed from the line lengths, as they
+            // are virtual offsets when reading BGZF files
+            let line_start = self.last_buf_offset;
+
+            if let Some(ref byte) = first {
                 match byte {
                     b'S' => {
                         segments.push((line_start, length));
@@ -194,8 +294,6 @@ impl MmapGFA {
                     }
                     _ => (),
                 };
-
-                line_start += line.len();
             } else {
                 break;
             }
@@ -235,6 +333,20 @@ impl MmapGFA {
         Ok(gfa_line)
     }
 
+    /// Parse the current line as a borrowed segment, link, or path,
+    /// without copying it. Returns `None` for other line types.
+    pub fn parse_current_line_borrowed(
+        &self,
+    ) -> Result<Option<BorrowedLine<'_>>> {
+        let line = self.current_line();
+        if line.is_empty() {
+            bail!("Line at offset {} is empty", self.last_buf_offset);
+        }
+
+        let gfa_line = self.parser.parse_borrowed_line(line)?;
+        Ok(gfa_line)
+    }
+
     pub fn iter_segments(&mut self, from_start: bool) -> SegmentIter<'_> {
         if from_start {
             self.cursor.set_position(0);
@@ -259,3 +371,74 @@ impl MmapGFA {
         PathIter { mmap: self, parser }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compressed_index() {
+        let mut plain = MmapGFA::new("./test/gfas/lil.gfa").unwrap();
+        let plain_index = plain.build_index().unwrap();
+
+        for path in ["./test/gfas/lil.gfa.gz", "./test/gfas/lil.bgzf.gfa.gz"] {
+            let mut mmap = MmapGFA::new(path).unwrap();
+            let index = mmap.build_index().unwrap();
+            assert_eq!(index.paths.len(), plain_index.paths.len());
+
+            for (&offset, &plain_offset) in
+                index.links.iter().zip(plain_index.links.iter()).rev()
+            {
+                let line = mmap.read_line_at(offset).unwrap().to_vec();
+                let plain_line = plain.read_line_at(plain_offset).unwrap();
+                assert_eq!(line, plain_line);
+            }
+
+            for (&(offset, _), &(plain_offset, _)) in
+                index.segments.iter().zip(plain_index.segments.iter())
+            {
+                let line = mmap.read_line_at(offset).unwrap().to_vec();
+                let plain_line = plain.read_line_at(plain_offset).unwrap();
+                assert_eq!(line, plain_line);
+            }
+        }
+    }
+
+    #[test]
+    fn bytes_and_position() {
+        let bytes = std::fs::read("./test/gfas/lil.gfa").unwrap();
+        let mut plain = MmapGFA::new("./test/gfas/lil.gfa").unwrap();
+        assert_eq!(plain.get_ref(), &bytes[..]);
+        let gzip = MmapGFA::new("./test/gfas/lil.gfa.gz").unwrap();
+        assert_eq!(gzip.get_ref(), &bytes[..]);
+        assert!(gzip.cursor().is_none());
+
+        let len = plain.next_line().unwrap().len();
+        assert_eq!(plain.position(), len as u64);
+        assert_eq!(plain.cursor().unwrap().position(), len as u64);
+        assert_eq!(plain.reset_position(), len as u64);
+        assert_eq!(plain.position(), 0);
+
+        plain.cursor_mut().unwrap().set_position(len as u64);
+        assert_eq!(plain.position(), len as u64);
+    }
+
+    #[test]
+    fn borrowed_lines() {
+        let mut mmap = MmapGFA::new("./test/gfas/lil.gfa").unwrap();
+        let index = mmap.build_index().unwrap();
+
+        mmap.read_line_at(index.paths[0]).unwrap();
+        let path = mmap.parse_current_line_borrowed().unwrap().unwrap();
+        let path = path.some_path().unwrap();
+        assert_eq!(path.path_name, "x");
+
+        let owned = mmap.parse_current_line().unwrap().some_path().unwrap();
+        assert_eq!(path.to_owned::<usize, ()>().unwrap(), owned);
+
+        mmap.read_line_at(index.links[0]).unwrap();
+        let link = mmap.parse_current_line_borrowed().unwrap().unwrap();
+        let owned = mmap.parse_current_line().unwrap();
+        assert_eq!(link.to_owned::<usize, ()>().unwrap(), owned);
+    }
+}
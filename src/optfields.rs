@@ -1,7 +1,11 @@
 use bstr::ByteSlice;
 
-use lazy_static::lazy_static;
-use regex::bytes::Regex;
+use std::borrow::Cow;
+
+use crate::parser::{scan, GFAFieldResult, OptFieldError, ParseFieldError};
+
+#[cfg(feature = "derive")]
+pub use gfa_derive::OptFields;
 
 /// These type aliases are useful for configuring the parsers, as the
 /// type of the optional field container must be given when creating a
@@ -19,8 +23,8 @@ pub struct OptField {
 
 /// enum for representing each of the SAM optional field types. The
 /// `B` type, which denotes either an integer or float array, is split
-/// in two variants, and they ignore the size modifiers in the spec,
-/// instead always holding i64 or f32.
+/// in two variants. Integer arrays keep the type of their elements,
+/// but always hold i64, while float arrays hold f32.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum OptFieldVal {
     A(u8),
@@ -28,13 +32,167 @@ pub enum OptFieldVal {
     Float(f32),
     Z(Vec<u8>),
     J(Vec<u8>),
-    H(Vec<u32>),
-    BInt(Vec<i64>),
+    H(Vec<u8>),
+    BInt(IntArrayType, Vec<i64>),
     BFloat(Vec<f32>),
 }
 
+impl OptFieldVal {
+    /// The type of the value, as written in the <TYPE> part of an
+    /// optional field.
+    pub fn type_byte(&self) -> u8 {
+        use OptFieldVal::*;
+        match self {
+            A(_) => b'A',
+            Int(_) => b'i',
+            Float(_) => b'f',
+            Z(_) => b'Z',
+            J(_) => b'J',
+            H(_) => b'H',
+            BInt(..) | BFloat(_) => b'B',
+        }
+    }
+}
+
+/// Conversion between Rust types and the values of optional fields,
+/// which is used by the optional field structs created with
+/// `#[derive(OptFields)]` to check that the values of the fields
+/// have the expected types.
+pub trait OptFieldValue: Sized + Clone {
+    /// Take the value of an optional field, or `None` if it doesn't
+    /// have the right type.
+    fn from_value(value: OptFieldVal) -> Option<Self>;
+
+    fn into_value(self) -> OptFieldVal;
+}
+
+macro_rules! impl_opt_field_value {
+    ($ty:ty, $var:path) => {
+        impl OptFieldValue for $ty {
+            #[inline]
+            fn from_value(value: OptFieldVal) -> Option<Self> {
+                if let $var(x) = value {
+                    Some(x)
+                } else {
+                    None
+                }
+            }
+
+            #[inline]
+            fn into_value(self) -> OptFieldVal {
+                $var(self)
+            }
+        }
+    };
+}
+
+impl_opt_field_value!(u8, OptFieldVal::A);
+impl_opt_field_value!(i64, OptFieldVal::Int);
+impl_opt_field_value!(f32, OptFieldVal::Float);
+impl_opt_field_value!(Vec<u8>, OptFieldVal::Z);
+impl_opt_field_value!(Vec<f32>, OptFieldVal::BFloat);
+
+/// Takes the JSON value of `J` fields.
+#[cfg(feature = "serde1")]
+impl OptFieldValue for serde_json::Value {
+    fn from_value(value: OptFieldVal) -> Option<Self> {
+        match value {
+            OptFieldVal::J(json) => serde_json::from_slice(&json).ok(),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> OptFieldVal {
+        // Serializing a JSON value can't fail
+        OptFieldVal::from_json(&self).unwrap()
+    }
+}
+
+/// Accepts values of any type.
+impl OptFieldValue for OptFieldVal {
+    #[inline]
+    fn from_value(value: OptFieldVal) -> Option<Self> {
+        Some(value)
+    }
+
+    #[inline]
+    fn into_value(self) -> OptFieldVal {
+        self
+    }
+}
+
+/// The types of the elements of integer `B` arrays, which decide the
+/// range of the elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IntArrayType {
+    /// `c`
+    I8,
+    /// `C`
+    U8,
+    /// `s`
+    I16,
+    /// `S`
+    U16,
+    /// `i`
+    I32,
+    /// `I`
+    U32,
+}
+
+impl IntArrayType {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        use IntArrayType::*;
+        match byte {
+            b'c' => Some(I8),
+            b'C' => Some(U8),
+            b's' => Some(I16),
+            b'S' => Some(U16),
+            b'i' => Some(I32),
+            b'I' => Some(U32),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte(&self) -> u8 {
+        use IntArrayType::*;
+        match self {
+            I8 => b'c',
+            U8 => b'C',
+            I16 => b's',
+            U16 => b'S',
+            I32 => b'i',
+            U32 => b'I',
+        }
+    }
+
+    /// Returns true if the value is in the range of the type.
+    pub fn contains(&self, value: i64) -> bool {
+        use IntArrayType::*;
+        let (min, max) = match self {
+            I8 => (i8::MIN.into(), i8::MAX.into()),
+            U8 => (0, u8::MAX.into()),
+            I16 => (i16::MIN.into(), i16::MAX.into()),
+            U16 => (0, u16::MAX.into()),
+            I32 => (i32::MIN.into(), i32::MAX.into()),
+            U32 => (0, u32::MAX.into()),
+        };
+        (min..=max).contains(&value)
+    }
+
+    /// The smallest type that contains all the values, preferring
+    /// unsigned types, or `None` if a value is out of range of every
+    /// type.
+    pub fn smallest_for(values: &[i64]) -> Option<Self> {
+        use IntArrayType::*;
+        [U8, I8, U16, I16, U32, I32]
+            .iter()
+            .copied()
+            .find(|ty| values.iter().all(|&v| ty.contains(v)))
+    }
+}
+
 impl OptField {
-    /// Panics if the provided tag doesn't match the regex
+    /// Panics if the provided tag doesn't match the pattern
     /// [A-Za-z][A-Za-z0-9].
     pub fn tag(t: &[u8]) -> [u8; 2] {
         assert_eq!(t.len(), 2);
@@ -52,76 +210,101 @@ impl OptField {
     }
 
     /// Parses an optional field from a bytestring in the format
-    /// <TAG>:<TYPE>:<VALUE>
-    pub fn parse(input: &[u8]) -> Option<Self> {
-        lazy_static! {
-            static ref RE_TAG: Regex =
-                Regex::new(r"(?-u)[A-Za-z][A-Za-z0-9]").unwrap();
-            static ref RE_CHAR: Regex = Regex::new(r"(?-u)[!-~]").unwrap();
-            static ref RE_INT: Regex = Regex::new(r"(?-u)[-+]?[0-9]+").unwrap();
-            static ref RE_FLOAT: Regex =
-                Regex::new(r"(?-u)[-+]?[0-9]*\.?[0-9]+([eE][-+]?[0-9]+)?")
-                    .unwrap();
-            static ref RE_STRING: Regex = Regex::new(r"(?-u)[ !-~]+").unwrap();
-            static ref RE_BYTES: Regex = Regex::new(r"(?-u)[0-9A-F]+").unwrap();
-        }
-
+    /// <TAG>:<TYPE>:<VALUE>. The error includes the tag, and why the
+    /// field couldn't be parsed.
+    pub fn parse(input: &[u8]) -> GFAFieldResult<Self> {
+        use OptFieldError as OFE;
         use OptFieldVal::*;
 
-        let o_tag = input.get(0..=1)?;
+        let o_tag = input.find_byte(b':').map_or(input, |ix| &input[..ix]);
+        let invalid =
+            |reason| ParseFieldError::InvalidOptField(o_tag.into(), reason);
 
-        let o_type = input.get(3)?;
+        let tag = match o_tag {
+            &[a, b] if a.is_ascii_alphabetic() && b.is_ascii_alphanumeric() => {
+                [a, b]
+            }
+            _ => return Err(invalid(OFE::InvalidTag)),
+        };
 
-        let o_contents = input.get(5..)?;
+        let (o_type, o_contents) = match &input[2..] {
+            [b':', o_type, b':', o_contents @ ..] => (*o_type, o_contents),
+            _ => return Err(invalid(OFE::InvalidFormat)),
+        };
 
         let o_val = match o_type {
             // char
-            b'A' => RE_CHAR.find(o_contents).map(|s| s.as_bytes()[0]).map(A),
+            b'A' => scan::find_char(o_contents).map(A),
             // int
-            b'i' => RE_INT
-                .find(o_contents)
-                .and_then(|s| s.as_bytes().to_str().ok())
+            b'i' => scan::find_int(o_contents)
+                .and_then(|s| s.to_str().ok())
                 .and_then(|s| s.parse().ok())
                 .map(Int),
             // float
-            b'f' => RE_FLOAT
-                .find(o_contents)
-                .and_then(|s| s.as_bytes().to_str().ok())
+            b'f' => scan::find_float(o_contents)
+                .and_then(|s| s.to_str().ok())
                 .and_then(|s| s.parse().ok())
                 .map(Float),
             // string
-            b'Z' => RE_STRING
-                .find(o_contents)
-                .map(|s| s.as_bytes().into())
-                .map(Z),
+            b'Z' => scan::find_string(o_contents).map(|s| Z(s.into())),
             // JSON string
-            b'J' => RE_STRING
-                .find(o_contents)
-                .map(|s| s.as_bytes().into())
-                .map(J),
+            b'J' => scan::find_string(o_contents).map(|s| J(s.into())),
             // bytearray
-            b'H' => RE_BYTES
-                .find(o_contents)
-                .and_then(|s| s.as_bytes().to_str().ok())
-                .map(|s| s.chars().filter_map(|c| c.to_digit(16)))
-                .map(|s| H(s.collect())),
+            b'H' => scan::find_hex(o_contents).and_then(parse_hex).map(H),
             // float or int array
             b'B' => {
-                let first = o_contents[0];
-                let rest = o_contents[1..]
-                    .split_str(b",")
-                    .filter_map(|s| s.as_bytes().to_str().ok());
-                if first == b'f' {
-                    Some(BFloat(rest.filter_map(|s| s.parse().ok()).collect()))
-                } else {
-                    Some(BInt(rest.filter_map(|s| s.parse().ok()).collect()))
-                }
+                let (&subtype, rest) = o_contents
+                    .split_first()
+                    .ok_or_else(|| invalid(OFE::MissingArrayType))?;
+                let array = match subtype {
+                    b'f' => parse_array(rest).map(BFloat),
+                    _ => {
+                        let int_type = IntArrayType::from_byte(subtype)
+                            .ok_or_else(|| {
+                                invalid(OFE::UnknownArrayType(subtype))
+                            })?;
+                        parse_array(rest)
+                            .filter(|xs: &Vec<i64>| {
+                                xs.iter().all(|&x| int_type.contains(x))
+                            })
+                            .map(|xs| BInt(int_type, xs))
+                    }
+                };
+                Some(array.ok_or_else(|| invalid(OFE::InvalidArrayElement))?)
             }
-            _ => None,
-        }?;
+            _ => return Err(invalid(OFE::UnknownType(o_type))),
+        };
+
+        let value = o_val.ok_or_else(|| invalid(OFE::InvalidValue(o_type)))?;
+        Ok(OptField { tag, value })
+    }
+}
 
-        Some(Self::new(o_tag, o_val))
+/// Parse the bytes of an `H` byte array, where each byte is written
+/// as two hexadecimal digits.
+fn parse_hex(input: &[u8]) -> Option<Vec<u8>> {
+    let digit = |b: u8| char::from(b).to_digit(16);
+    let pairs = input.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
     }
+    pairs
+        .map(|pair| Some((digit(pair[0])? * 16 + digit(pair[1])?) as u8))
+        .collect()
+}
+
+/// Parse the comma-separated elements of a `B` array, following the
+/// array type. Every element must be valid. The comma between the
+/// array type and the first element may be left out.
+fn parse_array<T: std::str::FromStr>(input: &[u8]) -> Option<Vec<T>> {
+    let input = input.strip_prefix(b",").unwrap_or(input);
+    if input.is_empty() {
+        return Some(Vec::new());
+    }
+    input
+        .split_str(b",")
+        .map(|s| s.to_str().ok()?.parse().ok())
+        .collect()
 }
 
 macro_rules! get_variant {
@@ -178,15 +361,175 @@ impl OptField {
 
     get_opt_field_val!(
         /// If this field contains a byte array, return a slice of it.
-        OptFieldVal::H,      ref &[u32], get_byte_array);
-
-    get_opt_field_val!(
-        /// If this field contains an array of integers, return a slice of it.
-        OptFieldVal::BInt,   ref &[i64], get_int_array);
+        OptFieldVal::H,      ref &[ u8], get_byte_array);
 
     get_opt_field_val!(
         /// If this field contains an array of floats, return a slice of it.
         OptFieldVal::BFloat, ref &[f32], get_float_array);
+
+    /// If this field contains an array of integers, return a slice of
+    /// it. The type of the elements can be found by matching on the
+    /// value.
+    pub fn get_int_array(&self) -> Option<&[i64]> {
+        if let OptFieldVal::BInt(_, x) = &self.value {
+            Some(x)
+        } else {
+            None
+        }
+    }
+}
+
+/// An optional field tag that's reserved by the GFA spec, or by rGFA,
+/// for the given line types, along with the type its values must
+/// have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReservedTag {
+    pub tag: [u8; 2],
+    pub field_type: u8,
+    /// The record type characters of the lines the tag is reserved
+    /// on.
+    pub line_types: &'static [u8],
+    pub description: &'static str,
+}
+
+macro_rules! reserved_tags {
+    ($($tag:literal, $type:literal, $lines:literal, $desc:literal;)*) => {
+        /// The reserved tags, in the order they're listed in the spec.
+        pub const RESERVED_TAGS: &[ReservedTag] = &[
+            $(ReservedTag {
+                tag: *$tag,
+                field_type: $type,
+                line_types: $lines,
+                description: $desc,
+            },)*
+        ];
+    };
+}
+
+reserved_tags!(
+    b"VN", b'Z', b"H",   "Version number";
+    b"TS", b'i', b"H",   "Trace spacing";
+    b"LN", b'i', b"S",   "Segment length";
+    b"RC", b'i', b"SLC", "Read count";
+    b"FC", b'i', b"SL",  "Fragment count";
+    b"KC", b'i', b"SL",  "k-mer count";
+    b"SH", b'H', b"S",   "SHA-256 checksum of the sequence";
+    b"UR", b'Z', b"S",   "URI or local file system path of the sequence";
+    b"MQ", b'i', b"L",   "Mapping quality";
+    b"NM", b'i', b"LC",  "Number of mismatches/gaps";
+    b"ID", b'Z', b"LC",  "Edge identifier";
+    b"SN", b'Z', b"S",   "Name of the stable sequence (rGFA)";
+    b"SO", b'i', b"S",   "Offset on the stable sequence (rGFA)";
+    b"SR", b'i', b"SL",  "Rank of the stable sequence (rGFA)";
+);
+
+impl ReservedTag {
+    /// Find the reserved tag matching `tag` on lines with the record
+    /// type `line_type`, if it's reserved.
+    pub fn get(line_type: u8, tag: &[u8]) -> Option<&'static ReservedTag> {
+        RESERVED_TAGS.iter().find(|reserved| {
+            reserved.tag == tag && reserved.line_types.contains(&line_type)
+        })
+    }
+
+    /// Check that an unparsed optional field on a line with the
+    /// record type `line_type` doesn't use a reserved tag with
+    /// another type than the one it's reserved with. Fields that
+    /// aren't in the <TAG>:<TYPE>:<VALUE> format pass the check, as
+    /// they fail to parse anyway.
+    pub fn check_field(line_type: u8, field: &[u8]) -> GFAFieldResult<()> {
+        let (tag, found) = match field {
+            [a, b, b':', found, b':', ..] => ([*a, *b], *found),
+            _ => return Ok(()),
+        };
+        match ReservedTag::get(line_type, &tag) {
+            Some(reserved) if reserved.field_type != found => {
+                Err(ParseFieldError::InvalidOptField(
+                    Box::from(&tag[..]),
+                    OptFieldError::ReservedTagType {
+                        expected: reserved.field_type,
+                        found,
+                    },
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "serde1")]
+impl OptFieldVal {
+    /// Serialize a value to a `J` field value. Characters outside of
+    /// printable ASCII are escaped, as they can't be in an optional
+    /// field.
+    pub fn from_json<S>(value: &S) -> serde_json::Result<Self>
+    where
+        S: serde::Serialize + ?Sized,
+    {
+        let json = serde_json::to_vec(value)?;
+        let mut escaped = String::with_capacity(json.len());
+        // Writing to a String can't fail
+        write_json(&json, &mut escaped).unwrap();
+        Ok(OptFieldVal::J(escaped.into_bytes()))
+    }
+}
+
+#[cfg(feature = "serde1")]
+impl OptField {
+    /// Create a new `J` field from a tag name and a value serialized
+    /// as JSON, panicking if the tag doesn't fulfill the requirements
+    /// of OptField::tag().
+    pub fn new_json<S>(tag: &[u8], value: &S) -> serde_json::Result<Self>
+    where
+        S: serde::Serialize + ?Sized,
+    {
+        Ok(OptField::new(tag, OptFieldVal::from_json(value)?))
+    }
+
+    /// If this field contains a JSON string, parse it into a JSON
+    /// value.
+    pub fn get_json_value(
+        &self,
+    ) -> Option<serde_json::Result<serde_json::Value>> {
+        self.get_json().map(serde_json::from_slice)
+    }
+}
+
+/// Write the bytes of a JSON string so that they only contain
+/// printable ASCII and spaces, as required in optional fields. Other
+/// characters can only be in JSON strings, where they're written as
+/// `\u` escapes, or be whitespace, which is written as spaces.
+fn write_json<W: std::fmt::Write>(
+    json: &[u8],
+    out: &mut W,
+) -> std::fmt::Result {
+    if json.iter().all(|b| matches!(b, b' '..=b'~')) {
+        return out.write_str(&json.to_str_lossy());
+    }
+
+    let (mut in_string, mut escaped) = (false, false);
+    for c in json.chars() {
+        match c {
+            ' '..='~' => {
+                if escaped {
+                    escaped = false;
+                } else if c == '"' {
+                    in_string = !in_string;
+                } else if c == '\\' && in_string {
+                    escaped = true;
+                }
+                out.write_char(c)?;
+            }
+            _ if in_string => {
+                escaped = false;
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    write!(out, "\\u{:04x}", unit)?;
+                }
+            }
+            _ => out.write_char(' ')?,
+        }
+    }
+    Ok(())
 }
 
 /// The Display implementation produces spec-compliant strings in the
@@ -203,24 +546,27 @@ impl std::fmt::Display for OptField {
             Int(x) => write!(f, "i:{}", x),
             Float(x) => write!(f, "f:{}", x),
             Z(x) => write!(f, "Z:{}", x.as_bstr()),
-            J(x) => write!(f, "J:{}", x.as_bstr()),
+            J(x) => {
+                write!(f, "J:")?;
+                write_json(x, f)
+            }
             H(x) => {
                 write!(f, "H:")?;
                 for a in x {
-                    write!(f, "{:x}", a)?
+                    write!(f, "{:02X}", a)?
                 }
                 Ok(())
             }
-            BInt(x) => {
-                write!(f, "B:I{}", x[0])?;
-                for a in x[1..].iter() {
+            BInt(int_type, x) => {
+                write!(f, "B:{}", char::from(int_type.as_byte()))?;
+                for a in x {
                     write!(f, ",{}", a)?
                 }
                 Ok(())
             }
             BFloat(x) => {
-                write!(f, "B:F{}", x[0])?;
-                for a in x[1..].iter() {
+                write!(f, "B:f")?;
+                for a in x {
                     write!(f, ",{}", a)?
                 }
                 Ok(())
@@ -234,43 +580,69 @@ impl std::fmt::Display for OptField {
 /// itself are generic over the optional fields, so the choice of
 /// OptFields implementor can impact memory usage, which optional
 /// fields are parsed, and possibly more in the future
+///
+/// Structs with a typed field for each tag can implement the trait
+/// with `#[derive(OptFields)]`, using the `derive` feature.
 pub trait OptFields: Sized + Default + Clone {
     /// Return the optional field with the given tag, if it exists.
-    fn get_field(&self, tag: &[u8]) -> Option<&OptField>;
+    /// The field is borrowed if it's stored as an `OptField`.
+    fn get_field(&self, tag: &[u8]) -> Option<Cow<'_, OptField>>;
 
-    /// Return a slice over all optional fields. NB: This may be
-    /// replaced by an iterator or something else in the future
-    fn fields(&self) -> &[OptField];
+    /// Return all optional fields, which are borrowed if they're
+    /// stored as a slice of `OptField`s.
+    fn fields(&self) -> Cow<'_, [OptField]>;
 
     /// Given an iterator over bytestrings, each expected to hold one
     /// optional field (in the <TAG>:<TYPE>:<VALUE> format), parse
-    /// them as optional fields to create a collection. Returns `Self`
-    /// rather than `Option<Self>` for now, but this may be changed to
-    /// become fallible in the future.
-    fn parse<T>(input: T) -> Self
+    /// them as optional fields to create a collection. Each field
+    /// that fails to parse is passed to `on_error`, which either
+    /// returns the error, failing the whole collection, or `Ok`, in
+    /// which case the field is skipped.
+    fn parse_with<T, E>(input: T, on_error: E) -> GFAFieldResult<Self>
+    where
+        T: IntoIterator,
+        T::Item: AsRef<[u8]>,
+        E: FnMut(ParseFieldError) -> GFAFieldResult<()>;
+
+    /// Parse a collection of optional fields, failing if any of them
+    /// fails to parse.
+    #[inline]
+    fn parse<T>(input: T) -> GFAFieldResult<Self>
     where
         T: IntoIterator,
-        T::Item: AsRef<[u8]>;
+        T::Item: AsRef<[u8]>,
+    {
+        Self::parse_with(input, Err)
+    }
+
+    /// Merge another collection of optional fields into this one,
+    /// with the fields in `other` replacing any fields with the same
+    /// tags.
+    fn merge(&mut self, other: Self);
 }
 
 /// This implementation is useful for performance if we don't actually
 /// need any optional fields. () takes up zero space, and all
 /// methods are no-ops.
 impl OptFields for () {
-    fn get_field(&self, _: &[u8]) -> Option<&OptField> {
+    fn get_field(&self, _: &[u8]) -> Option<Cow<'_, OptField>> {
         None
     }
 
-    fn fields(&self) -> &[OptField] {
-        &[]
+    fn fields(&self) -> Cow<'_, [OptField]> {
+        Cow::Borrowed(&[])
     }
 
-    fn parse<T>(_input: T) -> Self
+    fn parse_with<T, E>(_input: T, _on_error: E) -> GFAFieldResult<Self>
     where
         T: IntoIterator,
         T::Item: AsRef<[u8]>,
+        E: FnMut(ParseFieldError) -> GFAFieldResult<()>,
     {
+        Ok(())
     }
+
+    fn merge(&mut self, _other: Self) {}
 }
 
 /// Stores all the optional fields in a vector. `get_field` simply
@@ -278,22 +650,141 @@ impl OptFields for () {
 /// relatively small number of optional fields in practice, it should
 /// be efficient enough.
 impl OptFields for Vec<OptField> {
-    fn get_field(&self, tag: &[u8]) -> Option<&OptField> {
-        self.iter().find(|o| o.tag == tag)
+    fn get_field(&self, tag: &[u8]) -> Option<Cow<'_, OptField>> {
+        self.iter().find(|o| o.tag == tag).map(Cow::Borrowed)
     }
 
-    fn fields(&self) -> &[OptField] {
-        self.as_slice()
+    fn fields(&self) -> Cow<'_, [OptField]> {
+        Cow::Borrowed(self.as_slice())
     }
 
-    fn parse<T>(input: T) -> Self
+    fn parse_with<T, E>(input: T, mut on_error: E) -> GFAFieldResult<Self>
     where
         T: IntoIterator,
         T::Item: AsRef<[u8]>,
+        E: FnMut(ParseFieldError) -> GFAFieldResult<()>,
     {
-        input
-            .into_iter()
-            .filter_map(|f| OptField::parse(f.as_ref()))
-            .collect()
+        let mut fields = Vec::new();
+        for field in input {
+            match OptField::parse(field.as_ref()) {
+                Ok(field) => fields.push(field),
+                Err(err) => on_error(err)?,
+            }
+        }
+        Ok(fields)
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.retain(|f| other.iter().all(|o| o.tag != f.tag));
+        self.extend(other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn array_fields_round_trip() {
+        use OptFieldVal::*;
+
+        let fields: Vec<&[u8]> = vec![
+            b"AB:B:c,-128,127",
+            b"AC:B:S,0,65535",
+            b"AD:B:i",
+            b"AE:B:f,1.5,-2",
+            b"AF:B:f",
+            b"HX:H:00FF1A",
+        ];
+        for field in fields {
+            let parsed = OptField::parse(field).unwrap();
+            assert_eq!(parsed.to_string().as_bytes(), field);
+        }
+
+        let parse = |field: &[u8]| OptField::parse(field).ok().map(|f| f.value);
+        assert_eq!(
+            parse(b"AB:B:C1,2"),
+            Some(BInt(IntArrayType::U8, vec![1, 2]))
+        );
+        assert_eq!(parse(b"HX:H:0A1B"), Some(H(vec![0x0A, 0x1B])));
+        assert_eq!(parse(b"HX:H:1A2"), None);
+        assert_eq!(parse(b"AB:B:c,128"), None);
+        assert_eq!(parse(b"AB:B:C,-1"), None);
+        assert_eq!(parse(b"AB:B:x,1"), None);
+        assert_eq!(parse(b"AB:B:i,1,"), None);
+
+        assert_eq!(
+            IntArrayType::smallest_for(&[-1, 200]),
+            Some(IntArrayType::I16)
+        );
+        assert_eq!(IntArrayType::smallest_for(&[1 << 40]), None);
+    }
+
+    #[test]
+    fn json_fields_are_escaped() {
+        let field = OptField::new(b"JS", OptFieldVal::J(b"{}".to_vec()));
+        assert_eq!(field.to_string(), "JS:J:{}");
+
+        let json = "{\"a\":\t\"\u{e5}\u{1F600}\\\"\n\"}\n".as_bytes();
+        let field = OptField::new(b"JS", OptFieldVal::J(json.to_vec()));
+        assert_eq!(
+            field.to_string(),
+            r#"JS:J:{"a": "\u00e5\ud83d\ude00\"\u000a"} "#
+        );
+    }
+
+    #[cfg(feature = "serde1")]
+    #[test]
+    fn json_values() {
+        use serde_json::json;
+
+        let value = json!({"name": "sm\u{f6}rg\u{e5}s", "tags": [1, "\t"]});
+        let field = OptField::new_json(b"JS", &value).unwrap();
+        let written = field.to_string();
+        assert_eq!(
+            written,
+            r#"JS:J:{"name":"sm\u00f6rg\u00e5s","tags":[1,"\t"]}"#
+        );
+
+        let parsed = OptField::parse(written.as_bytes()).unwrap();
+        assert_eq!(parsed, field);
+        assert_eq!(parsed.get_json_value().unwrap().unwrap(), value);
+        assert_eq!(
+            serde_json::Value::from_value(parsed.value.clone()),
+            Some(value)
+        );
+
+        let field = OptField::new(b"JS", OptFieldVal::J(b"{".to_vec()));
+        assert!(field.get_json_value().unwrap().is_err());
+        let field = OptField::new(b"ZZ", OptFieldVal::Z(b"{}".to_vec()));
+        assert!(field.get_json_value().is_none());
+    }
+
+    #[test]
+    fn reserved_tag_types() {
+        use OptFieldError::ReservedTagType;
+
+        let field_type = |line_type, tag: &[u8]| {
+            ReservedTag::get(line_type, tag).map(|t| t.field_type)
+        };
+        assert_eq!(field_type(b'S', b"LN"), Some(b'i'));
+        assert_eq!(field_type(b'S', b"xx"), None);
+        // SO is only reserved on rGFA segments
+        assert_eq!(field_type(b'H', b"SO"), None);
+
+        assert!(ReservedTag::check_field(b'S', b"LN:i:4").is_ok());
+        assert!(ReservedTag::check_field(b'S', b"xx:Z:abc").is_ok());
+        assert!(ReservedTag::check_field(b'S', b"LN").is_ok());
+        assert!(ReservedTag::check_field(b'H', b"SO:Z:coordinate").is_ok());
+        assert!(matches!(
+            ReservedTag::check_field(b'S', b"SH:Z:abc"),
+            Err(ParseFieldError::InvalidOptField(
+                tag,
+                ReservedTagType {
+                    expected: b'H',
+                    found: b'Z'
+                }
+            )) if &tag[..] == b"SH"
+        ));
     }
 }
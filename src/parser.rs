@@ -1,23 +1,46 @@
 pub mod error;
+mod parallel;
+pub(crate) mod scan;
 
-pub use self::error::{GFAFieldResult, GFAResult, ParseError, ParseFieldError};
+pub use self::error::{
+    ErrorAction, ErrorKind, GFAFieldResult, GFAResult, Location, OptFieldError,
+    ParseError, ParseFieldError, SkippedLine, WarningCallback,
+};
 
-use bstr::ByteSlice;
-use lazy_static::lazy_static;
-use regex::bytes::Regex;
+use bstr::{BStr, ByteSlice};
+use fnv::FnvHashMap;
 
-use crate::{cigar::CIGAR, gfa::*, optfields::*};
+use crate::{cigar::CIGAR, gfa::borrowed::*, gfa::*, optfields::*};
 
 use crate::parser::error::ParserTolerance;
 
+use std::{
+    cell::{Cell, RefCell},
+    io::BufRead,
+};
+
 /// Builder struct for GFAParsers
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 pub struct GFAParserBuilder {
     pub segments: bool,
     pub links: bool,
+    pub jumps: bool,
     pub containments: bool,
     pub paths: bool,
+    pub walks: bool,
+    pub edges: bool,
+    pub fragments: bool,
+    pub gaps: bool,
+    pub groups: bool,
     pub tolerance: ParserTolerance,
+    /// Actions for specific kinds of errors, overriding the tolerance.
+    pub error_actions: FnvHashMap<ErrorKind, ErrorAction>,
+    pub warning_callback: Option<WarningCallback>,
+    /// Keep comments, empty lines, and lines with unknown record
+    /// types, and record the order of the lines.
+    pub preserve_lines: bool,
+    /// If set, only optional fields with these tags are parsed.
+    pub optional_tags: Option<Vec<[u8; 2]>>,
 }
 
 impl GFAParserBuilder {
@@ -26,9 +49,19 @@ impl GFAParserBuilder {
         GFAParserBuilder {
             segments: false,
             links: false,
+            jumps: false,
             containments: false,
             paths: false,
+            walks: false,
+            edges: false,
+            fragments: false,
+            gaps: false,
+            groups: false,
             tolerance: Default::default(),
+            error_actions: Default::default(),
+            warning_callback: None,
+            preserve_lines: false,
+            optional_tags: None,
         }
     }
 
@@ -37,9 +70,19 @@ impl GFAParserBuilder {
         GFAParserBuilder {
             segments: true,
             links: true,
+            jumps: true,
             containments: true,
             paths: true,
+            walks: true,
+            edges: true,
+            fragments: true,
+            gaps: true,
+            groups: true,
             tolerance: Default::default(),
+            error_actions: Default::default(),
+            warning_callback: None,
+            preserve_lines: false,
+            optional_tags: None,
         }
     }
 
@@ -53,11 +96,62 @@ impl GFAParserBuilder {
         self
     }
 
+    pub fn jumps(&mut self, include: bool) -> &mut Self {
+        self.jumps = include;
+        self
+    }
+
     pub fn paths(&mut self, include: bool) -> &mut Self {
         self.paths = include;
         self
     }
 
+    pub fn walks(&mut self, include: bool) -> &mut Self {
+        self.walks = include;
+        self
+    }
+
+    pub fn edges(&mut self, include: bool) -> &mut Self {
+        self.edges = include;
+        self
+    }
+
+    pub fn fragments(&mut self, include: bool) -> &mut Self {
+        self.fragments = include;
+        self
+    }
+
+    pub fn gaps(&mut self, include: bool) -> &mut Self {
+        self.gaps = include;
+        self
+    }
+
+    /// Include both ordered and unordered groups.
+    pub fn groups(&mut self, include: bool) -> &mut Self {
+        self.groups = include;
+        self
+    }
+
+    /// Keep comments, empty lines, and lines with unknown record
+    /// types as raw lines, rather than skipping them, and keep track
+    /// of the order of the lines in the parsed GFA. Together, these
+    /// let `writer::write_gfa` reproduce the input file.
+    pub fn preserve_lines(&mut self, preserve: bool) -> &mut Self {
+        self.preserve_lines = preserve;
+        self
+    }
+
+    /// Only parse the optional fields with the given tags, skipping
+    /// the others without parsing or allocating them. This keeps the
+    /// memory usage low when only a few optional fields are needed
+    /// from a large file. Panics if a tag isn't a valid tag, as in
+    /// `OptField::tag`.
+    pub fn optional_tags<S: AsRef<[u8]>>(&mut self, tags: &[S]) -> &mut Self {
+        let tags = tags.iter().map(|tag| OptField::tag(tag.as_ref()));
+        self.optional_tags = Some(tags.collect());
+        self
+    }
+
     pub fn error_tolerance(&mut self, tol: ParserTolerance) -> &mut Self {
         self.tolerance = tol;
         self
@@ -78,38 +172,109 @@ impl GFAParserBuilder {
         self
     }
 
-    pub fn build<N: SegmentId, T: OptFields>(self) -> GFAParser<N, T> {
+    /// Set the action to take for a kind of error, regardless of the
+    /// error tolerance.
+    pub fn error_action(
+        &mut self,
+        kind: ErrorKind,
+        action: ErrorAction,
+    ) -> &mut Self {
+        self.error_actions.insert(kind, action);
+        self
+    }
+
+    /// Set the callback that errors with the `Warn` action are passed
+    /// to, along with the location of the line that caused them.
+    /// Without a callback, those errors are ignored.
+    ///
+    /// When parsing in parallel, the callback may be called from
+    /// multiple threads, and in any order.
+    pub fn on_warning<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: FnMut(&ParseError, &Location) + Send + 'static,
+    {
+        self.warning_callback = Some(WarningCallback::new(callback));
+        self
+    }
+
+    pub fn build<N: SegmentId, T: OptFields>(&self) -> GFAParser<N, T> {
         GFAParser {
             segments: self.segments,
             links: self.links,
+            jumps: self.jumps,
             containments: self.containments,
             paths: self.paths,
+            walks: self.walks,
+            edges: self.edges,
+            fragments: self.fragments,
+            gaps: self.gaps,
+            groups: self.groups,
             tolerance: self.tolerance,
+            error_actions: self.error_actions.clone(),
+            warning_callback: self.warning_callback.clone(),
+            preserve_lines: self.preserve_lines,
+            optional_tags: self.optional_tags.clone(),
             _optional_fields: std::marker::PhantomData,
             _segment_names: std::marker::PhantomData,
         }
     }
 
-    pub fn build_usize_id<T: OptFields>(self) -> GFAParser<usize, T> {
+    pub fn build_usize_id<T: OptFields>(&self) -> GFAParser<usize, T> {
         self.build()
     }
 
-    pub fn build_bstr_id<T: OptFields>(self) -> GFAParser<Vec<u8>, T> {
+    pub fn build_bstr_id<T: OptFields>(&self) -> GFAParser<Vec<u8>, T> {
         self.build()
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct GFAParser<N: SegmentId, T: OptFields> {
     segments: bool,
     links: bool,
+    jumps: bool,
     containments: bool,
     paths: bool,
+    walks: bool,
+    edges: bool,
+    fragments: bool,
+    gaps: bool,
+    groups: bool,
     tolerance: ParserTolerance,
+    error_actions: FnvHashMap<ErrorKind, ErrorAction>,
+    warning_callback: Option<WarningCallback>,
+    preserve_lines: bool,
+    optional_tags: Option<Vec<[u8; 2]>>,
     _optional_fields: std::marker::PhantomData<T>,
     _segment_names: std::marker::PhantomData<N>,
 }
 
+// Implemented by hand as deriving would require `N` and `T` to be
+// Clone, even though they're only used as markers
+impl<N: SegmentId, T: OptFields> Clone for GFAParser<N, T> {
+    fn clone(&self) -> Self {
+        GFAParser {
+            segments: self.segments,
+            links: self.links,
+            jumps: self.jumps,
+            containments: self.containments,
+            paths: self.paths,
+            walks: self.walks,
+            edges: self.edges,
+            fragments: self.fragments,
+            gaps: self.gaps,
+            groups: self.groups,
+            tolerance: self.tolerance,
+            error_actions: self.error_actions.clone(),
+            warning_callback: self.warning_callback.clone(),
+            preserve_lines: self.preserve_lines,
+            optional_tags: self.optional_tags.clone(),
+            _optional_fields: std::marker::PhantomData,
+            _segment_names: std::marker::PhantomData,
+        }
+    }
+}
+
 impl<N: SegmentId, T: OptFields> Default for GFAParser<N, T> {
     fn default() -> Self {
         let config = GFAParserBuilder::all();
@@ -118,8 +283,8 @@ impl<N: SegmentId, T: OptFields> Default for GFAParser<N, T> {
 }
 
 impl<N: SegmentId, T: OptFields> GFAParser<N, T> {
-    /// Create a new GFAParser that will parse all four GFA line
-    /// types, and use the optional fields parser and storage `T`.
+    /// Create a new GFAParser that will parse all GFA line types, and
+    /// use the optional fields parser and storage `T`.
     pub fn new() -> Self {
         Default::default()
     }
@@ -130,8 +295,14 @@ impl<N: SegmentId, T: OptFields> GFAParser<N, T> {
             b'H' => false,
             b'S' => !self.segments,
             b'L' => !self.links,
+            b'J' => !self.jumps,
             b'P' => !self.paths,
             b'C' => !self.containments,
+            b'W' => !self.walks,
+            b'E' => !self.edges,
+            b'F' => !self.fragments,
+            b'G' => !self.gaps,
+            b'O' | b'U' => !self.groups,
             _ => true,
         }
     }
@@ -147,26 +318,107 @@ impl<N: SegmentId, T: OptFields> GFAParser<N, T> {
 
     #[inline]
     pub fn parse_gfa_line(&self, bytes: &[u8]) -> GFAResult<Line<N, T>> {
-        let line: &[u8] = bytes.trim_with(|c| c.is_ascii_whitespace());
+        self.parse_gfa_line_with_version(bytes, None)
+    }
+
+    /// Parse a line using the grammar of the given GFA version, which
+    /// decides whether segments are parsed as GFA1 or GFA2 segments.
+    /// Without a version, GFA2 segments are recognized by their
+    /// length field.
+    pub fn parse_gfa_line_with_version(
+        &self,
+        bytes: &[u8],
+        version: Option<GFAVersion>,
+    ) -> GFAResult<Line<N, T>> {
+        let mut skipped = Vec::new();
+        let line = self.parse_gfa_line_skipping(bytes, version, &mut skipped);
+        self.warn_skipped_fields(bytes, skipped, &Location::default());
+        line
+    }
 
-        let mut fields = line.split_str(b"\t");
-        let hdr = fields.next().ok_or(ParseError::EmptyLine)?;
+    /// Parse a line, where optional fields that fail to parse are
+    /// handled following their error action. The fields that are
+    /// skipped with a warning are added to `skipped`, along with
+    /// their columns, so that the warnings can include the location
+    /// of the line.
+    fn parse_gfa_line_skipping(
+        &self,
+        bytes: &[u8],
+        version: Option<GFAVersion>,
+        skipped: &mut SkippedFields,
+    ) -> GFAResult<Line<N, T>> {
+        let line: &[u8] = bytes.trim_with(|c| c.is_ascii_whitespace());
+        let gfa2_segments = match version {
+            Some(version) => version.is_gfa2(),
+            None => is_gfa2_segment(line),
+        };
 
-        let invalid_line =
-            |e: ParseFieldError| ParseError::invalid_line(e, bytes);
+        let count = Cell::new(0);
+        let mut fields = FieldCounter::new(scan::split_tabs(line), &count);
+        let hdr = fields
+            .next()
+            .ok_or_else(|| ParseError::EmptyLine(Location::default()))?;
+
+        let mut on_error =
+            |err: ParseFieldError| match self.kind_action(err.kind()) {
+                ErrorAction::Ignore => Ok(()),
+                ErrorAction::Warn => {
+                    skipped.push((err, count.get()));
+                    Ok(())
+                }
+                ErrorAction::Fail => Err(err),
+            };
+        let mut opt_fields = OptFieldParser {
+            tags: self.optional_tags.as_deref(),
+            line_type: hdr.first().copied().unwrap_or_default(),
+            check_reserved: self.kind_action(ErrorKind::ReservedTagType)
+                != ErrorAction::Ignore,
+            on_error: Some(&mut on_error),
+        };
 
+        let (f, o) = (&mut fields, &mut opt_fields);
         let line = match hdr {
-            b"H" => Header::parse_line(fields).map(Header::wrap),
-            b"S" => Segment::parse_line(fields).map(Segment::wrap),
-            b"L" => Link::parse_line(fields).map(Link::wrap),
-            b"C" => Containment::parse_line(fields).map(Containment::wrap),
-            b"P" => Path::parse_line(fields).map(Path::wrap),
-            _ => return Err(ParseError::UnknownLineType),
-        }
-        .map_err(invalid_line)?;
+            b"H" => Header::parse_line(f, o).map(Header::wrap),
+            b"S" if gfa2_segments => {
+                GFA2Segment::parse_line(f, o).map(GFA2Segment::wrap)
+            }
+            b"S" => Segment::parse_line(f, o).map(Segment::wrap),
+            b"L" => Link::parse_line(f, o).map(Link::wrap),
+            b"J" => Jump::parse_line(f, o).map(Jump::wrap),
+            b"C" => Containment::parse_line(f, o).map(Containment::wrap),
+            b"P" => Path::parse_line(f, o).map(Path::wrap),
+            b"W" => Walk::parse_line(f, o).map(Walk::wrap),
+            b"E" => Edge::parse_line(f, o).map(Edge::wrap),
+            b"F" => Fragment::parse_line(f, o).map(Fragment::wrap),
+            b"G" => Gap::parse_line(f, o).map(Gap::wrap),
+            b"O" => OrderedGroup::parse_line(f, o).map(OrderedGroup::wrap),
+            b"U" => UnorderedGroup::parse_line(f, o).map(UnorderedGroup::wrap),
+            _ => return Err(unknown_line_type()),
+        }
+        .map_err(|e| {
+            let column = fields.column_of(&e);
+            ParseError::invalid_line(e, bytes, column)
+        })?;
         Ok(line)
     }
 
+    /// Pass the optional fields that were skipped when parsing a line
+    /// to the warning callback.
+    fn warn_skipped_fields(
+        &self,
+        bytes: &[u8],
+        skipped: SkippedFields,
+        location: &Location,
+    ) {
+        if let Some(callback) = &self.warning_callback {
+            for (err, column) in skipped {
+                let err = ParseError::invalid_line(err, bytes, column)
+                    .at_line(location);
+                callback.warn(&err, err.location().unwrap_or(location));
+            }
+        }
+    }
+
     #[inline]
     pub fn parse_gfa_line_filtered(
         &self,
@@ -178,202 +430,1499 @@ impl<N: SegmentId, T: OptFields> GFAParser<N, T> {
             return Ok(None);
         }
 
-        let mut fields = line.split_str(b"\t");
-        let hdr = fields.next().ok_or(ParseError::EmptyLine)?;
+        self.parse_gfa_line(line).map(Some)
+    }
 
-        let invalid_line =
-            |e: ParseFieldError| ParseError::invalid_line(e, bytes);
+    /// Parse a segment, link, or path line without copying any of its
+    /// fields. Returns `None` if the line is of another line type, or
+    /// if its line type is filtered out. Note that segment IDs and
+    /// sequences aren't validated until the borrowed line is
+    /// converted to an owned line.
+    pub fn parse_borrowed_line<'a>(
+        &self,
+        bytes: &'a [u8],
+    ) -> GFAResult<Option<BorrowedLine<'a>>> {
+        let line: &[u8] = bytes.trim_with(|c| c.is_ascii_whitespace());
 
-        let line = match hdr {
-            b"H" => Header::parse_line(fields).map(Header::wrap),
-            b"S" => Segment::parse_line(fields).map(Segment::wrap),
-            b"L" => Link::parse_line(fields).map(Link::wrap),
-            b"C" => Containment::parse_line(fields).map(Containment::wrap),
-            b"P" => Path::parse_line(fields).map(Path::wrap),
-            _ => return Err(ParseError::UnknownLineType),
-        }
-        .map_err(invalid_line)?;
+        let hdr = line.find_byte(b'\t').map_or(line, |ix| &line[..ix]);
+        if let [c] = hdr {
+            if is_known_line_type(*c) && self.ignore_line_type(*c) {
+                return Ok(None);
+            }
+        }
+
+        let (line_type, max_fields) = match hdr {
+            b"" => return Err(ParseError::EmptyLine(Location::default())),
+            b"S" if is_gfa2_segment(line) => return Ok(None),
+            b"S" => (b'S', 4),
+            b"L" => (b'L', 7),
+            b"P" => (b'P', 5),
+            [c] if is_known_line_type(*c) => return Ok(None),
+            _ => return Err(unknown_line_type()),
+        };
+
+        // The optional fields are kept together as the last field
+        let count = Cell::new(0);
+        let mut fields =
+            FieldCounter::new(line.splitn(max_fields, |&b| b == b'\t'), &count);
+        fields.next();
+
+        let f = &mut fields;
+        let line = match line_type {
+            b'S' => SegmentRef::parse_line(f).map(BorrowedLine::Segment),
+            b'L' => LinkRef::parse_line(f).map(BorrowedLine::Link),
+            _ => PathRef::parse_line(f).map(BorrowedLine::Path),
+        }
+        .map_err(|e| {
+            let column = fields.column_of(&e);
+            ParseError::invalid_line(e, bytes, column)
+        })?;
         Ok(Some(line))
     }
 
+    /// The action to take for an error, which is the one set for its
+    /// kind of error, if any, and otherwise decided by the tolerance.
+    #[inline]
+    fn error_action(&self, err: &ParseError) -> ErrorAction {
+        match err.kind() {
+            Some(kind) => self.kind_action(kind),
+            None => ErrorAction::Fail,
+        }
+    }
+
+    #[inline]
+    fn kind_action(&self, kind: ErrorKind) -> ErrorAction {
+        self.error_actions
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| self.tolerance.default_action(kind))
+    }
+
+    /// Add the location of the line to an error caused by the line,
+    /// and skip the line if the error's action allows it, warning
+    /// about it first if the action is `Warn`.
+    fn handle_error<L>(
+        &self,
+        err: ParseError,
+        location: impl FnOnce() -> Location,
+    ) -> Option<GFAResult<L>> {
+        match self.report_error(err, location) {
+            (err, ErrorAction::Fail) => Some(Err(err)),
+            _ => None,
+        }
+    }
+
+    /// Add the location of the line to an error caused by the line,
+    /// and warn about it if its action is `Warn`. Returns the error
+    /// along with its action.
+    fn report_error(
+        &self,
+        err: ParseError,
+        location: impl FnOnce() -> Location,
+    ) -> (ParseError, ErrorAction) {
+        let line_loc = location();
+        let err = err.at_line(&line_loc);
+        let action = self.error_action(&err);
+        if action == ErrorAction::Warn {
+            if let Some(callback) = &self.warning_callback {
+                let loc = err.location().unwrap_or(&line_loc);
+                callback.warn(&err, loc);
+            }
+        }
+        (err, action)
+    }
+
+    /// Handle an error as in `handle_error`, but add it to `errors`
+    /// rather than failing, unless its action is `Ignore`. Returns
+    /// the action of the error.
+    fn collect_error(
+        &self,
+        err: ParseError,
+        location: &Location,
+        errors: &mut Vec<SkippedLine>,
+    ) -> ErrorAction {
+        let (error, action) = self.report_error(err, || location.clone());
+        if action != ErrorAction::Ignore {
+            errors.push(SkippedLine {
+                error,
+                location: location.clone(),
+            });
+        }
+        action
+    }
+
+    /// Check a parsed line against the version of the GFA, as known
+    /// from the earlier lines, and update the version. Conflicting
+    /// versions and lines that aren't in the version are handled like
+    /// other errors, but the line is kept unless it's rejected.
+    fn check_line(
+        &self,
+        mut line: Line<N, T>,
+        version: &mut VersionState,
+        location: impl FnOnce() -> Location,
+    ) -> GFAResult<Line<N, T>> {
+        if let Some(err) = version.check(&mut line) {
+            if let Some(Err(err)) = self.handle_error::<()>(err, location) {
+                return Err(err);
+            }
+        }
+        Ok(line)
+    }
+
+    /// Parse a borrowed line, skipping it if it can't be borrowed, if
+    /// it's a comment or an empty line, or if the error's action
+    /// allows skipping it. `location` is only called if the line fails
+    /// to parse.
+    #[inline]
+    fn parse_borrowed_tolerant<'a>(
+        &self,
+        bytes: &'a [u8],
+        location: impl FnOnce() -> Location,
+    ) -> Option<GFAResult<BorrowedLine<'a>>> {
+        let line: &[u8] = bytes.trim_with(|c| c.is_ascii_whitespace());
+        if matches!(line.first(), None | Some(b'#')) {
+            return None;
+        }
+        match self.parse_borrowed_line(bytes) {
+            Ok(line) => line.map(Ok),
+            Err(err) => self.handle_error(err, location),
+        }
+    }
+
+    /// Create an empty GFA to parse lines into, which keeps track of
+    /// the line order if the parser preserves lines.
+    fn new_gfa(&self) -> GFA<N, T> {
+        if self.preserve_lines {
+            GFA::with_line_order()
+        } else {
+            GFA::new()
+        }
+    }
+
+    /// Parse a single line, skipping it if its line type is filtered
+    /// out, or if it's a comment or an empty line. Lines with an
+    /// unknown line type are passed on to the parser, so that they
+    /// produce an error, unless the parser preserves them as raw
+    /// lines, along with comments and empty lines. The version
+    /// decides the grammar, as in `parse_gfa_line_with_version`, and
+    /// skipped optional fields are added to `skipped`, as in
+    /// `parse_gfa_line_skipping`.
+    #[inline]
+    fn parse_line_if_included(
+        &self,
+        bytes: &[u8],
+        version: Option<GFAVersion>,
+        skipped: &mut SkippedFields,
+    ) -> Option<GFAResult<Line<N, T>>> {
+        let line: &[u8] = bytes.trim_with(|c| c.is_ascii_whitespace());
+
+        if self.preserve_lines
+            && !line.first().is_some_and(|&c| is_known_line_type(c))
+        {
+            let raw = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+            return Some(Ok(Line::Raw(raw.to_vec())));
+        }
+
+        match line.first() {
+            None | Some(b'#') => None,
+            Some(&c) if is_known_line_type(c) && self.ignore_line_type(c) => {
+                None
+            }
+            Some(_) => {
+                Some(self.parse_gfa_line_skipping(line, version, skipped))
+            }
+        }
+    }
+
+    /// Parse a single line, skipping it if its line type is filtered
+    /// out, or if the line couldn't be parsed but the error's action
+    /// allows for continuing. `location` is only called if the line
+    /// fails to parse.
+    #[inline]
+    fn parse_line_tolerant(
+        &self,
+        bytes: &[u8],
+        version: Option<GFAVersion>,
+        location: impl FnOnce() -> Location,
+    ) -> Option<GFAResult<Line<N, T>>> {
+        let mut skipped = Vec::new();
+        match self.parse_line_if_included(bytes, version, &mut skipped)? {
+            Ok(line) => {
+                if !skipped.is_empty() {
+                    self.warn_skipped_fields(bytes, skipped, &location());
+                }
+                Some(Ok(line))
+            }
+            Err(err) => self.handle_error(err, location),
+        }
+    }
+
+    /// Parse the given lines into a GFA. The lines are expected to
+    /// be without their terminators, and each to have been followed
+    /// by a single newline, from which the byte offsets in the
+    /// locations of errors are computed.
     pub fn parse_lines<'a, I>(&self, lines: I) -> GFAResult<GFA<N, T>>
     where
         I: Iterator<Item = &'a [u8]> + 'a,
     {
-        let mut gfa = GFA::new();
-
-        for line in lines {
-            if !self.ignore_line(line) {
-                match self.parse_gfa_line(line.as_ref()) {
-                    Ok(parsed) => gfa.insert_line(parsed),
-                    Err(err) if err.can_safely_continue(&self.tolerance) => (),
-                    Err(err) => return Err(err),
-                };
+        let mut gfa = self.new_gfa();
+
+        let mut version = VersionState::default();
+        let mut next_offset = 0;
+
+        for (ix, line) in lines.enumerate() {
+            let offset = next_offset;
+            next_offset += line.len() as u64 + 1;
+            let location = || Location {
+                line: Some(ix + 1),
+                offset: Some(offset),
+                ..Location::default()
+            };
+            let parsed =
+                self.parse_line_tolerant(line, version.version, location);
+            if let Some(parsed) = parsed {
+                let parsed =
+                    self.check_line(parsed?, &mut version, location)?;
+                gfa.insert_line(parsed);
             }
         }
 
         Ok(gfa)
     }
 
+    /// Create an iterator that lazily parses the lines of the given
+    /// reader, which can be anything from a file to stdin, one line
+    /// at a time. Lines of the types that the parser has been
+    /// configured to ignore are skipped, as are lines that fail to
+    /// parse with an error the parser is configured to skip.
+    ///
+    /// Any other error is yielded by the iterator, which can continue
+    /// with the following lines, except for IO errors, after which
+    /// the iterator stops.
+    pub fn parse_reader<R: BufRead>(&self, reader: R) -> LineIter<R, N, T> {
+        LineIter {
+            reader,
+            parser: self.clone(),
+            buf: Vec::with_capacity(1024),
+            done: false,
+            file: None,
+            line: 0,
+            offset: 0,
+            next_offset: 0,
+            version: VersionState::default(),
+        }
+    }
+
+    /// Parse a GFA file, skipping every line that fails to parse
+    /// rather than stopping at the first error. Returns the GFA along
+    /// with the errors that would have stopped the parser or produced
+    /// a warning, so that all problems with a file can be reported at
+    /// once. IO errors still stop parsing.
+    pub fn parse_file_collect_errors<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> GFAResult<(GFA<N, T>, Vec<SkippedLine>)> {
+        let path = path.as_ref();
+        let reader = crate::compression::open_file(path)?;
+        self.parse_reader(reader)
+            .with_file_name(path.display().to_string())
+            .collect_errors()
+    }
+
+    /// Parse a GFA file, which is transparently decompressed if it's
+    /// gzip or BGZF compressed.
     pub fn parse_file<P: AsRef<std::path::Path>>(
         &self,
         path: P,
     ) -> Result<GFA<N, T>, ParseError> {
-        use {
-            bstr::io::BufReadExt,
-            std::{fs::File, io::BufReader},
-        };
+        let path = path.as_ref();
+        let reader = crate::compression::open_file(path)?;
+        let lines = self
+            .parse_reader(reader)
+            .with_file_name(path.display().to_string());
 
-        let file = File::open(path)?;
-        let lines = BufReader::new(file).byte_lines();
-
-        let mut gfa = GFA::new();
+        let mut gfa = self.new_gfa();
 
         for line in lines {
-            let line = line?;
-            match self.parse_gfa_line(line.as_ref()) {
-                Ok(parsed) => gfa.insert_line(parsed),
-                Err(err) if err.can_safely_continue(&self.tolerance) => (),
-                Err(err) => return Err(err),
-            };
+            gfa.insert_line(line?);
         }
 
         Ok(gfa)
     }
 }
 
-#[inline]
-pub const fn type_header() -> u8 {
-    b'H'
+/// Iterator over the parsed lines of a `BufRead`, created by
+/// `GFAParser::parse_reader`. Errors caused by a line include the
+/// line number and byte offset of the line.
+#[derive(Debug)]
+pub struct LineIter<R, N: SegmentId, T: OptFields> {
+    reader: R,
+    parser: GFAParser<N, T>,
+    buf: Vec<u8>,
+    done: bool,
+    file: Option<String>,
+    line: usize,
+    offset: u64,
+    next_offset: u64,
+    version: VersionState,
 }
 
-#[inline]
-pub const fn type_segment() -> u8 {
-    b'S'
-}
+impl<R: BufRead, N: SegmentId, T: OptFields> LineIter<R, N, T> {
+    /// Set the file name that's included in the location of errors.
+    pub fn with_file_name<S: Into<String>>(mut self, file: S) -> Self {
+        self.file = Some(file.into());
+        self
+    }
 
-#[inline]
-pub const fn type_link() -> u8 {
-    b'L'
-}
+    /// The location of the line that was read last.
+    pub fn location(&self) -> Location {
+        Location {
+            file: self.file.clone(),
+            line: Some(self.line),
+            offset: Some(self.offset),
+            column: None,
+        }
+    }
 
-#[inline]
-pub const fn type_path() -> u8 {
-    b'P'
-}
+    /// Parse all remaining lines into a GFA, handling errors as the
+    /// iterator does, except that errors that would stop the parser
+    /// only skip their line. Errors that stop the parser or produce a
+    /// warning, including optional fields skipped with a warning, are
+    /// returned along with the GFA, while ignored errors are left
+    /// out. IO errors still stop parsing.
+    pub fn collect_errors(
+        mut self,
+    ) -> GFAResult<(GFA<N, T>, Vec<SkippedLine>)> {
+        let mut gfa = self.parser.new_gfa();
+        let mut errors = Vec::new();
+
+        while let Some(read) = self.read_line() {
+            read?;
+            let location = self.location();
+            let mut skipped_fields = Vec::new();
+            let parsed = self.parser.parse_line_if_included(
+                &self.buf,
+                self.version.version,
+                &mut skipped_fields,
+            );
+            for (err, column) in skipped_fields {
+                let err = ParseError::invalid_line(err, &self.buf, column);
+                self.parser.collect_error(err, &location, &mut errors);
+            }
 
-#[inline]
-pub const fn type_containment() -> u8 {
-    b'C'
-}
+            let mut line = match parsed {
+                Some(Ok(line)) => line,
+                Some(Err(err)) => {
+                    self.parser.collect_error(err, &location, &mut errors);
+                    continue;
+                }
+                None => continue,
+            };
+            // Lines that don't agree with the version are kept unless
+            // the error would stop the parser
+            if let Some(err) = self.version.check(&mut line) {
+                let action =
+                    self.parser.collect_error(err, &location, &mut errors);
+                if action == ErrorAction::Fail {
+                    continue;
+                }
+            }
+            gfa.insert_line(line);
+        }
 
-#[inline]
-fn next_field<I, P>(mut input: I) -> GFAFieldResult<P>
-where
-    I: Iterator<Item = P>,
-    P: AsRef<[u8]>,
-{
-    input.next().ok_or(ParseFieldError::MissingFields)
-}
+        Ok((gfa, errors))
+    }
 
-#[inline]
-fn parse_orientation<I>(mut input: I) -> GFAFieldResult<Orientation>
-where
-    I: Iterator,
-    I::Item: AsRef<[u8]>,
-{
-    let next = next_field(&mut input)?;
-    let parsed = Orientation::from_bytes_plus_minus(next.as_ref());
-    Orientation::parse_error(parsed)
-}
+    /// Read the next line into the buffer, returning `None` at the
+    /// end of the input.
+    fn read_line(&mut self) -> Option<GFAResult<()>> {
+        if self.done {
+            return None;
+        }
+        self.buf.clear();
+        match self.reader.read_until(b'\n', &mut self.buf) {
+            Ok(0) => {
+                self.done = true;
+                None
+            }
+            Ok(n) => {
+                self.line += 1;
+                self.offset = self.next_offset;
+                self.next_offset += n as u64;
+                Some(Ok(()))
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err.into()))
+            }
+        }
+    }
 
-impl<T: OptFields> Header<T> {
-    #[inline]
-    fn wrap<N: SegmentId>(self) -> Line<N, T> {
-        Line::Header(self)
+    /// Parse the line in the buffer into an owned line, and check it
+    /// against the version known from the earlier lines.
+    fn parse_buffered_line(&mut self) -> Option<GFAResult<Line<N, T>>> {
+        let location = || self.location();
+        let version = self.version.version;
+        let parsed = self
+            .parser
+            .parse_line_tolerant(&self.buf, version, location)?;
+        // The version is taken out of the iterator so that the
+        // location can borrow it
+        let mut version = std::mem::take(&mut self.version);
+        let parsed = parsed.and_then(|line| {
+            let location = || self.location();
+            self.parser.check_line(line, &mut version, location)
+        });
+        self.version = version;
+        Some(parsed)
     }
 
-    #[inline]
-    fn parse_line<I>(mut input: I) -> GFAFieldResult<Self>
-    where
-        I: Iterator,
-        I::Item: AsRef<[u8]>,
-    {
-        let next = next_field(&mut input)?;
-        let version = OptField::parse(next.as_ref());
-        let version =
-            if let Some(OptFieldVal::Z(version)) = version.map(|v| v.value) {
-                Some(version)
-            } else {
-                None
-            };
+    /// Check a line of the given kind against the version, handling
+    /// the error as in `check_line`, and update the version.
+    fn check_line_kind(&mut self, kind: LineKind) -> GFAResult<()> {
+        if let Some(err) = self.version.check_kind(kind) {
+            let location = || self.location();
+            if let Some(Err(err)) =
+                self.parser.handle_error::<()>(err, location)
+            {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
 
-        let optional = T::parse(input);
+    /// Parse the next segment, link, or path as a borrowed line,
+    /// pointing into the iterator's line buffer. Other line types are
+    /// skipped, while errors are handled, and lines are checked
+    /// against the version, as when iterating over owned lines.
+    pub fn next_borrowed(&mut self) -> Option<GFAResult<BorrowedLine<'_>>> {
+        loop {
+            if let Err(err) = self.read_line()? {
+                return Some(Err(err));
+            }
+            let line = self.buf.trim_with(|c| c.is_ascii_whitespace());
+            let kind = line_kind(line, self.version.version)
+                .filter(|_| !self.parser.ignore_line(line));
+            match kind {
+                // Headers are parsed so that their versions are known
+                Some(LineKind::Header) => {
+                    if let Some(Err(err)) = self.parse_buffered_line() {
+                        return Some(Err(err));
+                    }
+                    continue;
+                }
+                Some(LineKind::Segment)
+                | Some(LineKind::Link)
+                | Some(LineKind::Path)
+                | None => (),
+                // Lines that can't be borrowed are only checked
+                // against the version
+                Some(kind) => {
+                    if let Err(err) = self.check_line_kind(kind) {
+                        return Some(Err(err));
+                    }
+                    continue;
+                }
+            }
 
-        Ok(Header { version, optional })
+            let location = || self.location();
+            match self.parser.parse_borrowed_tolerant(&self.buf, location) {
+                Some(Ok(_)) => (),
+                Some(Err(err)) => return Some(Err(err)),
+                None => continue,
+            }
+            if let Some(kind) = kind {
+                if let Err(err) = self.check_line_kind(kind) {
+                    return Some(Err(err));
+                }
+            }
+            break;
+        }
+        // A borrow of the buffer can't be returned from inside the
+        // loop that reads into it, so the line is parsed again once
+        // it's known to parse. Skipped lines are only parsed once.
+        self.parser.parse_borrowed_line(&self.buf).transpose()
     }
 }
 
-#[inline]
-fn parse_sequence<I>(input: &mut I) -> GFAFieldResult<Vec<u8>>
-where
-    I: Iterator,
-    I::Item: AsRef<[u8]>,
-{
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"(?-u)\*|[A-Za-z=.]+").unwrap();
+impl<R: BufRead, N: SegmentId, T: OptFields> Iterator for LineIter<R, N, T> {
+    type Item = GFAResult<Line<N, T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Err(err) = self.read_line()? {
+                return Some(Err(err));
+            }
+            if let Some(parsed) = self.parse_buffered_line() {
+                return Some(parsed);
+            }
+        }
     }
+}
 
-    let next = next_field(input)?;
-    RE.find(next.as_ref())
-        .map(|s| Vec::from(s.as_bytes()))
-        .ok_or(ParseFieldError::InvalidField("Sequence"))
+/// What's known about the version of the GFA being parsed, from the
+/// lines that have been parsed so far.
+#[derive(Debug, Default, Clone)]
+struct VersionState {
+    // The version of the first header line with a version
+    header: Option<Vec<u8>>,
+    // The version in the header, if it's a known version
+    version: Option<GFAVersion>,
+    // The version detected from the lines so far, which is only used
+    // if the header doesn't give the version
+    detected: Option<GFAVersion>,
 }
 
-impl<N: SegmentId, T: OptFields> Segment<N, T> {
-    #[inline]
-    fn wrap(self) -> Line<N, T> {
-        Line::Segment(self)
+impl VersionState {
+    /// Check a parsed line against the version, returning an error if
+    /// the line doesn't agree with it, and update the version.
+    ///
+    /// A header with a version that conflicts with the version of an
+    /// earlier header has its version removed. Other lines must be in
+    /// the version from the header, or, without one, can't mix GFA1
+    /// and GFA2 lines.
+    fn check<N, T: OptFields>(
+        &mut self,
+        line: &mut Line<N, T>,
+    ) -> Option<ParseError> {
+        match line {
+            Line::Header(header) => self.check_header(header),
+            line => self.check_kind(line.kind()),
+        }
     }
 
-    #[inline]
-    fn parse_line<I>(mut input: I) -> GFAFieldResult<Self>
-    where
-        I: Iterator,
-        I::Item: AsRef<[u8]>,
-    {
-        let name = N::parse_next(&mut input)?;
-        let sequence = parse_sequence(&mut input)?;
-        let optional = T::parse(input);
-        Ok(Segment {
-            name,
+    /// Check a line that isn't a header against the version, as in
+    /// `check`.
+    fn check_kind(&mut self, kind: LineKind) -> Option<ParseError> {
+        let introduced = GFAVersion::introducing(kind)?;
+        let not_in_version =
+            |v| ParseError::LineTypeNotInVersion(v, Location::default());
+
+        if let Some(version) = self.version {
+            return Some(not_in_version(version))
+                .filter(|_| !version.allows(kind));
+        }
+        match self.detected {
+            Some(detected) if detected.is_gfa2() != introduced.is_gfa2() => {
+                Some(not_in_version(detected))
+            }
+            detected => {
+                self.detected = detected.max(Some(introduced));
+                None
+            }
+        }
+    }
+
+    fn check_header<T: OptFields>(
+        &mut self,
+        header: &mut Header<T>,
+    ) -> Option<ParseError> {
+        let new = header.version.as_ref()?;
+        match &self.header {
+            None => {
+                self.version = GFAVersion::from_bytes(new);
+                self.header = Some(new.clone());
+                None
+            }
+            Some(first) if first == new => None,
+            Some(first) => {
+                let new = header.version.take()?;
+                Some(ParseError::VersionConflict(
+                    first.clone(),
+                    new,
+                    Location::default(),
+                ))
+            }
+        }
+    }
+}
+
+/// Optional fields that were skipped with a warning while parsing a
+/// line, along with their columns.
+type SkippedFields = Vec<(ParseFieldError, usize)>;
+
+/// Decides what happens to an optional field that fails to parse.
+/// Returning the error fails the line, while returning `Ok` skips the
+/// field.
+type OptFieldErrorFn<'a> =
+    dyn FnMut(ParseFieldError) -> GFAFieldResult<()> + 'a;
+
+/// Parses the optional fields of a line as configured in the parser.
+/// The default parses every field, and fails if any of them fails to
+/// parse.
+#[derive(Default)]
+struct OptFieldParser<'a> {
+    // Only the fields with these tags are parsed, if set
+    tags: Option<&'a [[u8; 2]]>,
+    // The record type of the line, and whether its fields are checked
+    // against the reserved tags, which keeps them if they pass the
+    // error handler
+    line_type: u8,
+    check_reserved: bool,
+    on_error: Option<&'a mut OptFieldErrorFn<'a>>,
+}
+
+impl<'a> OptFieldParser<'a> {
+    #[inline]
+    fn parse<T, I>(&mut self, input: I) -> GFAFieldResult<T>
+    where
+        T: OptFields,
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let (tags, line_type) = (self.tags, self.line_type);
+        let check_reserved = self.check_reserved;
+        // The handler is shared by the reserved tag check and the
+        // parser of the fields, which never call it at the same time
+        let handler = RefCell::new(&mut self.on_error);
+        let on_error = |err| match &mut **handler.borrow_mut() {
+            Some(on_error) => on_error(err),
+            None => Err(err),
+        };
+
+        let included = |field: &I::Item| match tags {
+            None => true,
+            Some(tags) => {
+                let tag = field.as_ref().get(..2);
+                tag.is_some_and(|tag| tags.iter().any(|t| t == tag))
+            }
+        };
+        // A failed check ends the fields early, and fails the line
+        let mut failed = None;
+        let checked = |field: I::Item| {
+            if check_reserved {
+                let checked =
+                    ReservedTag::check_field(line_type, field.as_ref());
+                if let Err(err) = checked.or_else(on_error) {
+                    failed = Some(err);
+                    return None;
+                }
+            }
+            Some(field)
+        };
+
+        let fields = input.filter(included).map_while(checked);
+        let parsed = T::parse_with(fields, on_error);
+        match failed {
+            Some(err) => Err(err),
+            None => parsed,
+        }
+    }
+}
+
+/// Wraps an iterator over the fields of a line, counting the fields
+/// that have been parsed so that the column of an error can be found.
+/// The count is shared, so that it can also be read while the fields
+/// are being parsed.
+struct FieldCounter<'c, I> {
+    fields: I,
+    count: &'c Cell<usize>,
+}
+
+impl<'c, I> FieldCounter<'c, I> {
+    #[inline]
+    fn new(fields: I, count: &'c Cell<usize>) -> Self {
+        FieldCounter { fields, count }
+    }
+
+    /// The 1-based column of the field that caused the error, which
+    /// is the last field that was parsed, or the one after it if it
+    /// was missing.
+    #[inline]
+    fn column_of(&self, error: &ParseFieldError) -> usize {
+        match error {
+            ParseFieldError::MissingFields => self.count.get() + 1,
+            _ => self.count.get().max(1),
+        }
+    }
+}
+
+impl<'c, I: Iterator> Iterator for FieldCounter<'c, I> {
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.fields.next();
+        if next.is_some() {
+            self.count.set(self.count.get() + 1);
+        }
+        next
+    }
+}
+
+#[inline]
+fn unknown_line_type() -> ParseError {
+    ParseError::UnknownLineType(Location {
+        column: Some(1),
+        ..Location::default()
+    })
+}
+
+#[inline]
+fn is_known_line_type(line_type: u8) -> bool {
+    matches!(
+        line_type,
+        b'H' | b'S'
+            | b'L'
+            | b'J'
+            | b'P'
+            | b'C'
+            | b'W'
+            | b'E'
+            | b'F'
+            | b'G'
+            | b'O'
+            | b'U'
+    )
+}
+
+#[inline]
+pub const fn type_header() -> u8 {
+    b'H'
+}
+
+#[inline]
+pub const fn type_segment() -> u8 {
+    b'S'
+}
+
+#[inline]
+pub const fn type_link() -> u8 {
+    b'L'
+}
+
+#[inline]
+pub const fn type_jump() -> u8 {
+    b'J'
+}
+
+#[inline]
+pub const fn type_path() -> u8 {
+    b'P'
+}
+
+#[inline]
+pub const fn type_containment() -> u8 {
+    b'C'
+}
+
+#[inline]
+pub const fn type_walk() -> u8 {
+    b'W'
+}
+
+#[inline]
+pub const fn type_edge() -> u8 {
+    b'E'
+}
+
+#[inline]
+pub const fn type_fragment() -> u8 {
+    b'F'
+}
+
+#[inline]
+pub const fn type_gap() -> u8 {
+    b'G'
+}
+
+#[inline]
+pub const fn type_ordered_group() -> u8 {
+    b'O'
+}
+
+#[inline]
+pub const fn type_unordered_group() -> u8 {
+    b'U'
+}
+
+#[inline]
+fn next_field<I, P>(mut input: I) -> GFAFieldResult<P>
+where
+    I: Iterator<Item = P>,
+    P: AsRef<[u8]>,
+{
+    input.next().ok_or(ParseFieldError::MissingFields)
+}
+
+#[inline]
+fn parse_orientation<I>(mut input: I) -> GFAFieldResult<Orientation>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    let next = next_field(&mut input)?;
+    let parsed = Orientation::from_bytes_plus_minus(next.as_ref());
+    Orientation::parse_error(parsed)
+}
+
+/// Parses a GFA2 reference, i.e. a segment ID immediately followed
+/// by its orientation, as in `s1+`.
+#[inline]
+fn parse_reference<I, N>(mut input: I) -> GFAFieldResult<(N, Orientation)>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+    N: SegmentId,
+{
+    let next = next_field(&mut input)?;
+    let (last, name) = next
+        .as_ref()
+        .split_last()
+        .ok_or(ParseFieldError::MissingFields)?;
+    let orient =
+        Orientation::parse_error(Orientation::from_bytes_plus_minus([*last]))?;
+    let id = N::parse_id(name).ok_or(N::ERROR)?;
+    Ok((id, orient))
+}
+
+/// Parses a GFA2 position, i.e. an integer optionally followed by `$`.
+#[inline]
+fn parse_position<I>(
+    mut input: I,
+    field: &'static str,
+) -> GFAFieldResult<Position>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    let next = next_field(&mut input)?;
+    Position::parse(next.as_ref()).ok_or(ParseFieldError::InvalidField(field))
+}
+
+/// Parses a GFA2 optional ID, where `*` denotes the absence of an ID.
+#[inline]
+fn parse_optional_id<I>(mut input: I) -> GFAFieldResult<Option<Vec<u8>>>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    let next = next_field(&mut input)?;
+    match next.as_ref() {
+        b"*" => Ok(None),
+        id => Vec::<u8>::parse_id(id)
+            .map(Some)
+            .ok_or(ParseFieldError::InvalidField("ID")),
+    }
+}
+
+/// GFA1 segments have the sequence as their second field, which can
+/// never be a number, while GFA2 segments have the segment length
+/// in its place.
+#[inline]
+fn is_gfa2_segment(line: &[u8]) -> bool {
+    scan::split_tabs(line).nth(2).is_some_and(|field| {
+        !field.is_empty() && field.iter().all(u8::is_ascii_digit)
+    })
+}
+
+/// The kind of a line from its record type, with segments parsed
+/// as GFA1 or GFA2 segments as in `parse_gfa_line_with_version`.
+/// Returns `None` for comments, empty lines, and unknown line types.
+fn line_kind(line: &[u8], version: Option<GFAVersion>) -> Option<LineKind> {
+    let hdr = line.find_byte(b'\t').map_or(line, |ix| &line[..ix]);
+    let kind = match hdr {
+        b"H" => LineKind::Header,
+        b"S" => {
+            let gfa2 = match version {
+                Some(version) => version.is_gfa2(),
+                None => is_gfa2_segment(line),
+            };
+            if gfa2 {
+                LineKind::GFA2Segment
+            } else {
+                LineKind::Segment
+            }
+        }
+        b"L" => LineKind::Link,
+        b"J" => LineKind::Jump,
+        b"C" => LineKind::Containment,
+        b"P" => LineKind::Path,
+        b"W" => LineKind::Walk,
+        b"E" => LineKind::Edge,
+        b"F" => LineKind::Fragment,
+        b"G" => LineKind::Gap,
+        b"O" => LineKind::OrderedGroup,
+        b"U" => LineKind::UnorderedGroup,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+impl<T: OptFields> Header<T> {
+    #[inline]
+    fn wrap<N: SegmentId>(self) -> Line<N, T> {
+        Line::Header(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        // The version can be in any of the fields
+        let fields: Vec<_> = input.collect();
+        if fields.is_empty() {
+            return Err(ParseFieldError::MissingFields);
+        }
+        let is_version =
+            |field: &&I::Item| field.as_ref().starts_with(b"VN:Z:");
+
+        let version = fields
+            .iter()
+            .find(is_version)
+            .map(|field| field.as_ref()[5..].to_vec());
+
+        let optional = fields.iter().filter(|f| !is_version(f));
+        let optional = opt_fields.parse(optional)?;
+
+        Ok(Header { version, optional })
+    }
+}
+
+#[inline]
+fn parse_sequence<I>(input: &mut I) -> GFAFieldResult<Vec<u8>>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    let next = next_field(input)?;
+    scan::find_sequence(next.as_ref())
+        .map(Vec::from)
+        .ok_or(ParseFieldError::InvalidField("Sequence"))
+}
+
+impl<N: SegmentId, T: OptFields> Segment<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::Segment(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let name = N::parse_next(&mut input)?;
+        let sequence = parse_sequence(&mut input)?;
+        let optional = opt_fields.parse(input)?;
+        Ok(Segment {
+            name,
+            sequence,
+            optional,
+        })
+    }
+}
+
+impl<N: SegmentId, T: OptFields> Link<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::Link(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let from_segment = N::parse_next(&mut input)?;
+        let from_orient = parse_orientation(&mut input)?;
+        let to_segment = N::parse_next(&mut input)?;
+        let to_orient = parse_orientation(&mut input)?;
+
+        let overlap = next_field(&mut input)?.as_ref().into();
+
+        let optional = opt_fields.parse(input)?;
+        Ok(Link {
+            from_segment,
+            from_orient,
+            to_segment,
+            to_orient,
+            overlap,
+            optional,
+        })
+    }
+}
+
+impl<N: SegmentId, T: OptFields> Jump<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::Jump(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let from_segment = N::parse_next(&mut input)?;
+        let from_orient = parse_orientation(&mut input)?;
+        let to_segment = N::parse_next(&mut input)?;
+        let to_orient = parse_orientation(&mut input)?;
+
+        let distance = next_field(&mut input)?;
+        let distance = match distance.as_ref() {
+            b"*" => None,
+            dist => Some(dist.to_str()?.parse()?),
+        };
+
+        let optional = opt_fields.parse(input)?;
+        Ok(Jump {
+            from_segment,
+            from_orient,
+            to_segment,
+            to_orient,
+            distance,
+            optional,
+        })
+    }
+}
+
+impl<N: SegmentId, T: OptFields> Containment<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::Containment(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let container_name = N::parse_next(&mut input)?;
+        let container_orient = parse_orientation(&mut input)?;
+
+        let contained_name = N::parse_next(&mut input)?;
+        let contained_orient = parse_orientation(&mut input)?;
+
+        let pos = next_field(&mut input)?;
+        let pos = pos.as_ref().to_str()?.parse()?;
+
+        let overlap = next_field(&mut input)?.as_ref().into();
+
+        let optional = opt_fields.parse(input)?;
+
+        Ok(Containment {
+            container_name,
+            container_orient,
+            contained_name,
+            contained_orient,
+            overlap,
+            pos,
+            optional,
+        })
+    }
+}
+
+impl<N: SegmentId, T: OptFields> Path<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::Path(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        // Use the SegmentId parser for the path name as well; it's
+        // just always Vec<u8>
+        let path_name = Vec::<u8>::parse_next(&mut input)?;
+
+        let segment_names =
+            next_field(&mut input).map(|bs| Vec::<u8>::from(bs.as_ref()))?;
+
+        // Overlaps are separated by `;` rather than `,` when the
+        // corresponding steps are connected by a jump
+        let overlaps = next_field(&mut input)?
+            .as_ref()
+            .split(|&b| is_step_separator(b))
+            .map(|bs| {
+                if bs == b"*" {
+                    None
+                } else {
+                    CIGAR::from_bytestring(bs)
+                }
+            })
+            .collect();
+
+        /*
+        // special case for throwing away the *s if all overlaps are *
+        // faster but will require some more logic in the Path
+        // interface, so I'm sticking to the other one for now
+
+        let overlaps = next_field(&mut input)?;
+        let overlaps = overlaps
+            .as_ref()
+            .split_str(b",")
+            // .map(Vec<u8>::from)
+            .map(|bs| {
+                if bs == b"*" {
+                    None
+                } else {
+                    CIGAR::from_bytestring(bs)
+                }
+            });
+
+        let (overlaps, none): (Vec<_>, Vec<_>) =
+            overlaps.partition(|x| x.is_some());
+        let overlaps = if overlaps.is_empty() { none } else { overlaps };
+        */
+        // .collect();
+
+        let optional = opt_fields.parse(input)?;
+
+        Ok(Path::new(path_name, segment_names, overlaps, optional))
+    }
+}
+
+/// Parses a walk coordinate, where `*` denotes a missing coordinate.
+#[inline]
+fn parse_walk_coord<I>(mut input: I) -> GFAFieldResult<Option<usize>>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    let next = next_field(&mut input)?;
+    match next.as_ref() {
+        b"*" => Ok(None),
+        coord => Ok(Some(coord.to_str()?.parse()?)),
+    }
+}
+
+impl<N: SegmentId, T: OptFields> Walk<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::Walk(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let sample_id = Vec::<u8>::parse_next(&mut input)?;
+
+        let hap_index = next_field(&mut input)?;
+        let hap_index = hap_index.as_ref().to_str()?.parse()?;
+
+        let seq_id = Vec::<u8>::parse_next(&mut input)?;
+
+        let seq_start = parse_walk_coord(&mut input)?;
+        let seq_end = parse_walk_coord(&mut input)?;
+
+        let walk = next_field(&mut input)?;
+        let walk = walk.as_ref();
+        let valid_walk = matches!(walk.first(), Some(b'>') | Some(b'<'))
+            && walk_steps(walk).all(|(name, _)| !name.is_empty());
+        if !valid_walk {
+            return Err(ParseFieldError::InvalidField("Walk"));
+        }
+
+        let optional = opt_fields.parse(input)?;
+
+        Ok(Walk::new(
+            sample_id,
+            hap_index,
+            seq_id,
+            seq_start,
+            seq_end,
+            walk.into(),
+            optional,
+        ))
+    }
+}
+
+impl<N: SegmentId, T: OptFields> GFA2Segment<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::GFA2Segment(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let name = N::parse_next(&mut input)?;
+        let length = next_field(&mut input)?;
+        let length = length.as_ref().to_str()?.parse()?;
+        let sequence = parse_sequence(&mut input)?;
+        let optional = opt_fields.parse(input)?;
+        Ok(GFA2Segment {
+            name,
+            length,
+            sequence,
+            optional,
+        })
+    }
+}
+
+impl<N: SegmentId, T: OptFields> Edge<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::Edge(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let id = parse_optional_id(&mut input)?;
+        let (sid1, sid1_orient) = parse_reference(&mut input)?;
+        let (sid2, sid2_orient) = parse_reference(&mut input)?;
+
+        let beg1 = parse_position(&mut input, "beg1")?;
+        let end1 = parse_position(&mut input, "end1")?;
+        let beg2 = parse_position(&mut input, "beg2")?;
+        let end2 = parse_position(&mut input, "end2")?;
+
+        let alignment = next_field(&mut input)?.as_ref().into();
+
+        let optional = opt_fields.parse(input)?;
+        Ok(Edge {
+            id,
+            sid1,
+            sid1_orient,
+            sid2,
+            sid2_orient,
+            beg1,
+            end1,
+            beg2,
+            end2,
+            alignment,
+            optional,
+        })
+    }
+}
+
+impl<N: SegmentId, T: OptFields> Fragment<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::Fragment(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let segment = N::parse_next(&mut input)?;
+        let (external, external_orient) =
+            parse_reference::<_, Vec<u8>>(&mut input)?;
+
+        let sbeg = parse_position(&mut input, "sbeg")?;
+        let send = parse_position(&mut input, "send")?;
+        let fbeg = parse_position(&mut input, "fbeg")?;
+        let fend = parse_position(&mut input, "fend")?;
+
+        let alignment = next_field(&mut input)?.as_ref().into();
+
+        let optional = opt_fields.parse(input)?;
+        Ok(Fragment {
+            segment,
+            external,
+            external_orient,
+            sbeg,
+            send,
+            fbeg,
+            fend,
+            alignment,
+            optional,
+        })
+    }
+}
+
+impl<N: SegmentId, T: OptFields> Gap<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::Gap(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let id = parse_optional_id(&mut input)?;
+        let (sid1, sid1_orient) = parse_reference(&mut input)?;
+        let (sid2, sid2_orient) = parse_reference(&mut input)?;
+
+        let distance = next_field(&mut input)?;
+        let distance = distance.as_ref().to_str()?.parse()?;
+
+        let variance = next_field(&mut input)?;
+        let variance = match variance.as_ref() {
+            b"*" => None,
+            var => Some(var.to_str()?.parse()?),
+        };
+
+        let optional = opt_fields.parse(input)?;
+        Ok(Gap {
+            id,
+            sid1,
+            sid1_orient,
+            sid2,
+            sid2_orient,
+            distance,
+            variance,
+            optional,
+        })
+    }
+}
+
+impl<N: SegmentId, T: OptFields> OrderedGroup<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::OrderedGroup(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let id = parse_optional_id(&mut input)?;
+        let items =
+            next_field(&mut input).map(|bs| Vec::<u8>::from(bs.as_ref()))?;
+        let optional = opt_fields.parse(input)?;
+        Ok(OrderedGroup::new(id, items, optional))
+    }
+}
+
+impl<N: SegmentId, T: OptFields> UnorderedGroup<N, T> {
+    #[inline]
+    fn wrap(self) -> Line<N, T> {
+        Line::UnorderedGroup(self)
+    }
+
+    #[inline]
+    fn parse_line<I>(
+        mut input: I,
+        opt_fields: &mut OptFieldParser<'_>,
+    ) -> GFAFieldResult<Self>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let id = parse_optional_id(&mut input)?;
+        let items =
+            next_field(&mut input).map(|bs| Vec::<u8>::from(bs.as_ref()))?;
+        let optional = opt_fields.parse(input)?;
+        Ok(UnorderedGroup::new(id, items, optional))
+    }
+}
+
+/// Takes the next field of a line being parsed into a borrowed line.
+#[inline]
+fn next_borrowed_field<'a, I>(input: I) -> GFAFieldResult<&'a BStr>
+where
+    I: Iterator<Item = &'a [u8]>,
+{
+    next_field(input).map(|f| f.as_bstr())
+}
+
+/// Takes the remaining, unparsed optional fields of a line being
+/// parsed into a borrowed line.
+#[inline]
+fn borrowed_optional<'a, I>(mut input: I) -> &'a BStr
+where
+    I: Iterator<Item = &'a [u8]>,
+{
+    input.next().unwrap_or_default().as_bstr()
+}
+
+impl<'a> SegmentRef<'a> {
+    #[inline]
+    fn parse_line<I>(mut input: I) -> GFAFieldResult<Self>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        let name = next_borrowed_field(&mut input)?;
+        let sequence = next_borrowed_field(&mut input)?;
+        let optional = borrowed_optional(input);
+        Ok(SegmentRef {
+            name,
             sequence,
             optional,
         })
     }
-}
 
-impl<N: SegmentId, T: OptFields> Link<N, T> {
-    #[inline]
-    fn wrap(self) -> Line<N, T> {
-        Line::Link(self)
+    /// Parse the segment ID, sequence and optional fields, producing
+    /// the owned segment.
+    pub fn to_owned<N: SegmentId, T: OptFields>(
+        &self,
+    ) -> GFAFieldResult<Segment<N, T>> {
+        let fields = [self.name.as_bytes(), self.sequence.as_bytes()];
+        Segment::parse_line(
+            fields.iter().copied().chain(self.optional_fields()),
+            &mut OptFieldParser::default(),
+        )
     }
+}
 
+impl<'a> LinkRef<'a> {
     #[inline]
     fn parse_line<I>(mut input: I) -> GFAFieldResult<Self>
     where
-        I: Iterator,
-        I::Item: AsRef<[u8]>,
+        I: Iterator<Item = &'a [u8]>,
     {
-        let from_segment = N::parse_next(&mut input)?;
+        let from_segment = next_borrowed_field(&mut input)?;
         let from_orient = parse_orientation(&mut input)?;
-        let to_segment = N::parse_next(&mut input)?;
+        let to_segment = next_borrowed_field(&mut input)?;
         let to_orient = parse_orientation(&mut input)?;
-
-        let overlap = next_field(&mut input)?.as_ref().into();
-
-        let optional = T::parse(input);
-        Ok(Link {
+        let overlap = next_borrowed_field(&mut input)?;
+        let optional = borrowed_optional(input);
+        Ok(LinkRef {
             from_segment,
             from_orient,
             to_segment,
@@ -382,103 +1931,68 @@ impl<N: SegmentId, T: OptFields> Link<N, T> {
             optional,
         })
     }
-}
 
-impl<N: SegmentId, T: OptFields> Containment<N, T> {
-    #[inline]
-    fn wrap(self) -> Line<N, T> {
-        Line::Containment(self)
+    /// Parse the segment IDs and optional fields, producing the owned
+    /// link.
+    pub fn to_owned<N: SegmentId, T: OptFields>(
+        &self,
+    ) -> GFAFieldResult<Link<N, T>> {
+        Ok(Link {
+            from_segment: N::parse_id(self.from_segment).ok_or(N::ERROR)?,
+            from_orient: self.from_orient,
+            to_segment: N::parse_id(self.to_segment).ok_or(N::ERROR)?,
+            to_orient: self.to_orient,
+            overlap: self.overlap.to_vec(),
+            optional: T::parse(self.optional_fields())?,
+        })
     }
+}
 
+impl<'a> PathRef<'a> {
     #[inline]
     fn parse_line<I>(mut input: I) -> GFAFieldResult<Self>
     where
-        I: Iterator,
-        I::Item: AsRef<[u8]>,
+        I: Iterator<Item = &'a [u8]>,
     {
-        let container_name = N::parse_next(&mut input)?;
-        let container_orient = parse_orientation(&mut input)?;
-
-        let contained_name = N::parse_next(&mut input)?;
-        let contained_orient = parse_orientation(&mut input)?;
-
-        let pos = next_field(&mut input)?;
-        let pos = pos.as_ref().to_str()?.parse()?;
-
-        let overlap = next_field(&mut input)?.as_ref().into();
-
-        let optional = T::parse(input);
-
-        Ok(Containment {
-            container_name,
-            container_orient,
-            contained_name,
-            contained_orient,
-            overlap,
-            pos,
+        let path_name = next_borrowed_field(&mut input)?;
+        let segment_names = next_borrowed_field(&mut input)?;
+        let overlaps = next_borrowed_field(&mut input)?;
+        let optional = borrowed_optional(input);
+        Ok(PathRef {
+            path_name,
+            segment_names,
+            overlaps,
             optional,
         })
     }
-}
 
-impl<N: SegmentId, T: OptFields> Path<N, T> {
-    #[inline]
-    fn wrap(self) -> Line<N, T> {
-        Line::Path(self)
+    /// Parse the path name, overlaps and optional fields, producing
+    /// the owned path.
+    pub fn to_owned<N: SegmentId, T: OptFields>(
+        &self,
+    ) -> GFAFieldResult<Path<N, T>> {
+        let fields = [
+            self.path_name.as_bytes(),
+            self.segment_names.as_bytes(),
+            self.overlaps.as_bytes(),
+        ];
+        Path::parse_line(
+            fields.iter().copied().chain(self.optional_fields()),
+            &mut OptFieldParser::default(),
+        )
     }
+}
 
-    #[inline]
-    fn parse_line<I>(mut input: I) -> GFAFieldResult<Self>
-    where
-        I: Iterator,
-        I::Item: AsRef<[u8]>,
-    {
-        // Use the SegmentId parser for the path name as well; it's
-        // just always Vec<u8>
-        let path_name = Vec::<u8>::parse_next(&mut input)?;
-
-        let segment_names =
-            next_field(&mut input).map(|bs| Vec::<u8>::from(bs.as_ref()))?;
-
-        let overlaps = next_field(&mut input)?
-            .as_ref()
-            .split_str(b",")
-            .map(|bs| {
-                if bs == b"*" {
-                    None
-                } else {
-                    CIGAR::from_bytestring(bs)
-                }
-            })
-            .collect();
-
-        /*
-        // special case for throwing away the *s if all overlaps are *
-        // faster but will require some more logic in the Path
-        // interface, so I'm sticking to the other one for now
-
-        let overlaps = next_field(&mut input)?;
-        let overlaps = overlaps
-            .as_ref()
-            .split_str(b",")
-            // .map(Vec<u8>::from)
-            .map(|bs| {
-                if bs == b"*" {
-                    None
-                } else {
-                    CIGAR::from_bytestring(bs)
-                }
-            });
-
-        let (overlaps, none): (Vec<_>, Vec<_>) =
-            overlaps.partition(|x| x.is_some());
-        let overlaps = if overlaps.is_empty() { none } else { overlaps };
-        */
-        // .collect();
-
-        let optional = T::parse(input);
-
-        Ok(Path::new(path_name, segment_names, overlaps, optional))
+impl<'a> BorrowedLine<'a> {
+    /// Convert the borrowed line into the corresponding owned line.
+    pub fn to_owned<N: SegmentId, T: OptFields>(
+        &self,
+    ) -> GFAFieldResult<Line<N, T>> {
+        match self {
+            BorrowedLine::Segment(s) => s.to_owned().map(Line::Segment),
+            BorrowedLine::Link(l) => l.to_owned().map(Line::Link),
+            BorrowedLine::Path(p) => p.to_owned().map(Line::Path),
+        }
     }
 }
 
@@ -495,7 +2009,7 @@ mod tests {
         };
 
         let result: GFAFieldResult<Header<()>> =
-            Header::parse_line([hdr].iter());
+            Header::parse_line([hdr].iter(), &mut OptFieldParser::default());
 
         match result {
             Err(_) => {
@@ -518,7 +2032,7 @@ mod tests {
         };
 
         let fields = link.split_terminator('\t');
-        let result = Link::parse_line(fields);
+        let result = Link::parse_line(fields, &mut OptFieldParser::default());
 
         match result {
             Err(_) => {
@@ -543,7 +2057,8 @@ mod tests {
         };
 
         let fields = cont.split_terminator('\t');
-        let result = Containment::parse_line(fields);
+        let result =
+            Containment::parse_line(fields, &mut OptFieldParser::default());
         match result {
             Err(_) => {
                 panic!("Error parsing containment");
@@ -556,7 +2071,7 @@ mod tests {
     fn can_parse_path() {
         let path = "14\t11+,12-,13+\t4M,5M";
 
-        let cigars = vec![b"4M", b"5M"]
+        let cigars = [b"4M", b"5M"]
             .iter()
             .map(|bs| CIGAR::from_bytestring(&bs[..]))
             .collect();
@@ -566,7 +2081,7 @@ mod tests {
 
         let fields = path.split_terminator('\t');
 
-        let result = Path::parse_line(fields);
+        let result = Path::parse_line(fields, &mut OptFieldParser::default());
 
         match result {
             Err(_) => {
@@ -576,11 +2091,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_parse_jump() {
+        let jump = "11\t+\t12\t-\t*\tSC:i:1";
+
+        let result: GFAFieldResult<Jump<Vec<u8>, OptionalFields>> =
+            Jump::parse_line(
+                jump.split_terminator('\t'),
+                &mut OptFieldParser::default(),
+            );
+
+        match result {
+            Err(_) => {
+                panic!("Error parsing jump");
+            }
+            Ok(j) => {
+                assert_eq!(j.from_segment, b"11");
+                assert_eq!(j.to_orient, Orientation::Backward);
+                assert_eq!(j.distance, None);
+                assert!(j.is_shortcut());
+            }
+        }
+
+        let jump = "11\t+\t12\t-\t-150";
+        let result: GFAFieldResult<Jump<Vec<u8>, ()>> = Jump::parse_line(
+            jump.split_terminator('\t'),
+            &mut OptFieldParser::default(),
+        );
+        assert_eq!(result.unwrap().distance, Some(-150));
+    }
+
+    #[test]
+    fn can_parse_path_with_jumps() {
+        let path = "14\t11+;12-,13+\t*;4M";
+
+        let path_: Path<Vec<u8>, _> = Path::new(
+            "14".into(),
+            "11+;12-,13+".into(),
+            vec![None, CIGAR::from_bytestring(b"4M")],
+            (),
+        );
+
+        let result = Path::parse_line(
+            path.split_terminator('\t'),
+            &mut OptFieldParser::default(),
+        );
+        assert_eq!(result.unwrap(), path_);
+    }
+
     #[test]
     fn can_parse_gfa_lines() {
         let parser = GFAParser::new();
         let gfa: GFA<Vec<u8>, ()> =
-            parser.parse_file(&"./test/gfas/lil.gfa").unwrap();
+            parser.parse_file("./test/gfas/lil.gfa").unwrap();
 
         let num_segs = gfa.segments.len();
         let num_links = gfa.links.len();
@@ -593,10 +2156,100 @@ mod tests {
         assert_eq!(num_paths, 3);
     }
 
+    #[test]
+    fn can_parse_walk() {
+        let walk = "HG002\t1\tchr1\t0\t*\t>11<12>13";
+        let walk_: Walk<Vec<u8>, _> = Walk::new(
+            "HG002".into(),
+            1,
+            "chr1".into(),
+            Some(0),
+            None,
+            ">11<12>13".into(),
+            (),
+        );
+
+        let fields = walk.split_terminator('\t');
+        let result = Walk::parse_line(fields, &mut OptFieldParser::default());
+
+        match result {
+            Err(_) => {
+                panic!("Error parsing walk");
+            }
+            Ok(w) => assert_eq!(w, walk_),
+        }
+
+        let bad_walk = "HG002\t1\tchr1\t0\t*\t11+,12-";
+        let result: GFAFieldResult<Walk<Vec<u8>, ()>> = Walk::parse_line(
+            bad_walk.split_terminator('\t'),
+            &mut OptFieldParser::default(),
+        );
+        assert!(matches!(result, Err(ParseFieldError::InvalidField("Walk"))));
+    }
+
+    #[test]
+    fn can_parse_edge() {
+        let edge = "1_to_2\t1+\t2-\t110\t122$\t0\t12\t12M";
+        let edge_: Edge<Vec<u8>, ()> = Edge {
+            id: Some("1_to_2".into()),
+            sid1: "1".into(),
+            sid1_orient: Orientation::Forward,
+            sid2: "2".into(),
+            sid2_orient: Orientation::Backward,
+            beg1: Position::new(110, false),
+            end1: Position::new(122, true),
+            beg2: Position::new(0, false),
+            end2: Position::new(12, false),
+            alignment: "12M".into(),
+            optional: (),
+        };
+
+        let fields = edge.split_terminator('\t');
+        let result = Edge::parse_line(fields, &mut OptFieldParser::default());
+
+        match result {
+            Err(_) => {
+                panic!("Error parsing edge");
+            }
+            Ok(e) => assert_eq!(e, edge_),
+        }
+    }
+
+    #[test]
+    fn can_parse_gfa2_lines() {
+        let parser = GFAParser::new();
+        let gfa: GFA<Vec<u8>, ()> =
+            parser.parse_file("./test/gfas/gfa2.gfa").unwrap();
+
+        assert_eq!(gfa.header.version, Some("2.0".into()));
+        assert_eq!(gfa.segments.len(), 0);
+        assert_eq!(gfa.gfa2_segments.len(), 5);
+        assert_eq!(gfa.edges.len(), 3);
+        assert_eq!(gfa.fragments.len(), 1);
+        assert_eq!(gfa.gaps.len(), 2);
+        assert_eq!(gfa.ordered_groups.len(), 1);
+        assert_eq!(gfa.unordered_groups.len(), 1);
+
+        assert_eq!(gfa.gfa2_segments[1].length, 26);
+        assert_eq!(gfa.edges[1].id, None);
+        assert_eq!(gfa.gaps[0].variance, None);
+        assert_eq!(gfa.gaps[1].variance, Some(50));
+
+        let group: Vec<_> = gfa.ordered_groups[0].iter().collect();
+        assert_eq!(
+            group,
+            vec![
+                ("11".into(), Orientation::Forward),
+                ("11_to_13".into(), Orientation::Forward),
+                ("13".into(), Orientation::Forward),
+            ]
+        );
+    }
+
     #[test]
     fn gfa_usize_parser_can_fail() {
         let usize_parser: GFAParser<usize, OptionalFields> = GFAParser::new();
-        let usize_gfa = usize_parser.parse_file(&"./test/gfas/diatom.gfa");
+        let usize_gfa = usize_parser.parse_file("./test/gfas/diatom.gfa");
 
         assert!(usize_gfa.is_err());
 
@@ -604,8 +2257,569 @@ mod tests {
 
         assert!(matches!(
             err,
-            ParseError::InvalidLine(ParseFieldError::UintIdError, _)
+            ParseError::InvalidLine(ParseFieldError::UintIdError, _, _)
+        ));
+    }
+
+    #[test]
+    fn can_parse_compressed_files() {
+        let parser: GFAParser<usize, ()> = GFAParser::new();
+        let plain = parser.parse_file("./test/gfas/lil.gfa").unwrap();
+        let gzip = parser.parse_file("./test/gfas/lil.gfa.gz").unwrap();
+        let bgzf = parser.parse_file("./test/gfas/lil.bgzf.gfa.gz").unwrap();
+
+        assert_eq!(plain, gzip);
+        assert_eq!(plain, bgzf);
+    }
+
+    #[test]
+    fn can_parse_borrowed_lines() {
+        let parser: GFAParser<usize, OptionalFields> = GFAParser::new();
+        let gfa = parser.parse_file("./test/gfas/lil.gfa").unwrap();
+
+        let file = std::fs::File::open("./test/gfas/lil.gfa").unwrap();
+        let mut lines = parser.parse_reader(std::io::BufReader::new(file));
+
+        let mut segments = Vec::new();
+        let mut links = Vec::new();
+        let mut paths = Vec::new();
+        while let Some(line) = lines.next_borrowed() {
+            match line.unwrap() {
+                BorrowedLine::Segment(s) => {
+                    segments.push(s.to_owned().unwrap())
+                }
+                BorrowedLine::Link(l) => links.push(l.to_owned().unwrap()),
+                BorrowedLine::Path(p) => {
+                    assert_eq!(p.iter().count(), 10);
+                    paths.push(p.to_owned().unwrap())
+                }
+            }
+        }
+        assert_eq!(segments, gfa.segments);
+        assert_eq!(links, gfa.links);
+        assert_eq!(paths, gfa.paths);
+
+        let line = b"S\t11\tACCTT\tLN:i:5\tRC:i:123";
+        let seg = parser.parse_borrowed_line(line).unwrap().unwrap();
+        let seg = seg.some_segment().unwrap();
+        assert_eq!(seg.name, "11");
+        assert_eq!(seg.sequence, "ACCTT");
+        assert_eq!(seg.optional_fields().count(), 2);
+
+        let line = b"L\t11\t+\t12\tx\t4M";
+        let err = parser.parse_borrowed_line(line).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidLine(ParseFieldError::OrientationError, ..)
+        ));
+        assert_eq!(err.location().unwrap().column, Some(5));
+
+        let line = b"C\t1\t+\t2\t-\t110\t100M";
+        assert!(parser.parse_borrowed_line(line).unwrap().is_none());
+    }
+
+    #[test]
+    fn errors_include_location() {
+        let input: &[u8] = b"H\tVN:Z:1.0
+S\t1\tACGT
+S\t2\tGGTA
+L\t1\t+\t2
+";
+        let parser: GFAParser<usize, ()> = GFAParser::new();
+        let err = parser
+            .parse_reader(input)
+            .with_file_name("input.gfa")
+            .find_map(Result::err)
+            .unwrap();
+
+        let loc = err.location().unwrap();
+        assert_eq!(loc.line, Some(4));
+        assert_eq!(loc.offset, Some(29));
+        assert_eq!(loc.column, Some(5));
+        assert!(err.to_string().starts_with("input.gfa:4:5: "));
+
+        let err = parser.parse_lines(ByteSlice::lines(input)).unwrap_err();
+        let loc = err.location().unwrap();
+        assert_eq!(loc.line, Some(4));
+        assert_eq!(loc.offset, Some(29));
+        assert!(err.to_string().starts_with("4:5: "));
+
+        let err = parser.parse_gfa_line(b"L\t1\t+\t2\t*\t0M").unwrap_err();
+        assert!(err.to_string().starts_with("5: "));
+
+        let loc = Location {
+            file: Some("input.gfa".to_string()),
+            offset: Some(29),
+            ..Location::default()
+        };
+        assert_eq!(loc.to_string(), "input.gfa:byte 29");
+        let loc = Location {
+            offset: Some(29),
+            ..Location::default()
+        };
+        assert!(!loc.is_empty());
+        assert_eq!(loc.to_string(), "byte 29");
+    }
+
+    #[test]
+    fn can_collect_all_errors() {
+        let input: &[u8] = b"H\tVN:Z:1.0
+S\t1\tACGT
+X\tunknown
+S\tx\tGGTA
+S\t2\tGGTA
+L\t1\t+\t2\t?\t0M
+L\t1\t+\t2\t-\t0M
+";
+        let parser: GFAParser<usize, ()> =
+            GFAParserBuilder::all().pedantic_errors().build();
+        let (gfa, skipped) =
+            parser.parse_reader(input).collect_errors().unwrap();
+
+        assert_eq!(gfa.segments.len(), 2);
+        assert_eq!(gfa.links.len(), 1);
+
+        let lines: Vec<_> = skipped.iter().map(|s| s.location.line).collect();
+        assert_eq!(lines, vec![Some(3), Some(4), Some(6)]);
+        assert!(matches!(skipped[0].error, ParseError::UnknownLineType(_)));
+        assert!(matches!(
+            skipped[1].error,
+            ParseError::InvalidLine(ParseFieldError::UintIdError, ..)
+        ));
+        assert_eq!(skipped[2].error.location().unwrap().column, Some(5));
+
+        let parser: GFAParser<Vec<u8>, ()> = GFAParser::new();
+        let (gfa, skipped) = parser
+            .parse_file_collect_errors("./test/gfas/lil.gfa")
+            .unwrap();
+        assert_eq!(gfa, parser.parse_file("./test/gfas/lil.gfa").unwrap());
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn collect_errors_follows_error_actions() {
+        let input: &[u8] = b"H\tVN:Z:1.0
+# comment
+S\t1\tACGT
+
+S\t2\tAC\tLN:i:x
+S\tx\tGGTA
+H\tVN:Z:2.0
+E\t*\t1+\t2+\t0\t2$\t0\t2$\t2M
+";
+        let parser: GFAParser<usize, OptionalFields> = GFAParser::new();
+        let (gfa, errors) =
+            parser.parse_reader(input).collect_errors().unwrap();
+
+        // The comment and the empty line are ignored, while the line
+        // with an invalid field, the header with a conflicting
+        // version, and the edge that isn't in GFA1 are kept
+        assert_eq!(gfa.segments.len(), 2);
+        assert!(gfa.segments[1].optional.is_empty());
+        assert_eq!(gfa.header.version, Some(b"1.0".to_vec()));
+        assert_eq!(gfa.edges.len(), 1);
+
+        let lines: Vec<_> = errors.iter().map(|e| e.location.line).collect();
+        assert_eq!(lines, vec![Some(5), Some(6), Some(7)]);
+        assert!(matches!(
+            errors[0].error,
+            ParseError::InvalidLine(ParseFieldError::InvalidOptField(..), ..)
+        ));
+        let loc = errors[0].error.location().unwrap();
+        assert_eq!((loc.line, loc.column), (Some(5), Some(4)));
+        assert!(matches!(
+            errors[1].error,
+            ParseError::InvalidLine(ParseFieldError::UintIdError, ..)
+        ));
+        assert!(matches!(errors[2].error, ParseError::VersionConflict(..)));
+    }
+
+    #[test]
+    fn can_merge_headers() {
+        let input: &[u8] = b"H\tVN:Z:1.0
+S\t1\tACGT
+H\tff:Z:tool\tVN:Z:1.0
+H\tff:Z:other\tpp:i:1
+";
+        let parser: GFAParser<usize, OptionalFields> = GFAParser::new();
+        let gfa = parser.parse_lines(ByteSlice::lines(input)).unwrap();
+        assert_eq!(gfa.header.version, Some(b"1.0".to_vec()));
+        let tags: Vec<_> =
+            gfa.header.optional.iter().map(|f| f.to_string()).collect();
+        assert_eq!(tags, vec!["ff:Z:other", "pp:i:1"]);
+
+        // A header line without a version doesn't set one
+        let gfa = parser.parse_lines([&b"H\tpp:i:1"[..]].iter().copied());
+        assert_eq!(gfa.unwrap().header.version, None);
+    }
+
+    #[test]
+    fn conflicting_versions() {
+        use std::sync::{Arc, Mutex};
+
+        let input: &[u8] = b"H\tVN:Z:1.0
+S\t1\tACGT
+H\tVN:Z:2.0\tff:Z:tool
+";
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let parser: GFAParser<usize, OptionalFields> = {
+            let warnings = warnings.clone();
+            GFAParserBuilder::all()
+                .on_warning(move |err, loc| {
+                    warnings.lock().unwrap().push((err.kind(), loc.line))
+                })
+                .build()
+        };
+
+        let gfa = parser.parse_lines(ByteSlice::lines(input)).unwrap();
+        assert_eq!(gfa.header.version, Some(b"1.0".to_vec()));
+        assert_eq!(gfa.header.optional.len(), 1);
+        let gfa_reader: GFA<usize, OptionalFields> = parser
+            .parse_reader(input)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+            .into_iter()
+            .fold(GFA::new(), |mut gfa, line| {
+                gfa.insert_line(line);
+                gfa
+            });
+        assert_eq!(gfa, gfa_reader);
+        assert_eq!(gfa, parser.parse_bytes_par(input).unwrap());
+        assert_eq!(
+            *warnings.lock().unwrap(),
+            vec![(Some(ErrorKind::VersionConflict), Some(3)); 3]
+        );
+
+        let parser: GFAParser<usize, OptionalFields> =
+            GFAParserBuilder::all().pedantic_errors().build();
+        let err = parser.parse_lines(ByteSlice::lines(input)).unwrap_err();
+        assert!(matches!(err, ParseError::VersionConflict(..)));
+        assert_eq!(err.location().unwrap().line, Some(3));
+        let err = parser.parse_bytes_par(input).unwrap_err();
+        assert_eq!(err.location().unwrap().line, Some(3));
+    }
+
+    #[test]
+    fn version_decides_grammar() {
+        let parser: GFAParser<Vec<u8>, ()> = GFAParser::new();
+
+        let segment = b"S\t1\t4\tACGT";
+        let line = parser.parse_gfa_line(segment).unwrap();
+        assert!(matches!(line, Line::GFA2Segment(_)));
+        let v2 = Some(GFAVersion::V2_0);
+        let line = parser.parse_gfa_line_with_version(segment, v2).unwrap();
+        assert!(matches!(line, Line::GFA2Segment(_)));
+        let v1 = Some(GFAVersion::V1_0);
+        let err = parser.parse_gfa_line_with_version(segment, v1).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidLine(ParseFieldError::InvalidField(_), ..)
         ));
+
+        let input: &[u8] = b"H\tVN:Z:1.0\nS\t1\t4\tACGT\n";
+        assert!(parser.parse_lines(ByteSlice::lines(input)).is_err());
+        assert!(parser.parse_bytes_par(input).is_err());
+
+        let input: &[u8] = b"H\tVN:Z:2.0\nS\t1\t4\tACGT\n";
+        let gfa = parser.parse_lines(ByteSlice::lines(input)).unwrap();
+        assert_eq!(gfa.gfa2_segments.len(), 1);
+        assert_eq!(gfa.version(), GFAVersion::V2_0);
+
+        let gfa = parser.parse_file("./test/gfas/jumps.gfa").unwrap();
+        assert_eq!(gfa.version(), GFAVersion::V1_2);
+
+        // Without a version, it's detected from the line types
+        let input: &[u8] = b"S\t1\tACGT\nW\tsample\t1\tchr1\t*\t*\t>1\n";
+        let gfa = parser.parse_lines(ByteSlice::lines(input)).unwrap();
+        assert_eq!(gfa.header.version, None);
+        assert_eq!(gfa.version(), GFAVersion::V1_1);
+    }
+
+    #[test]
+    fn pedantic_rejects_lines_not_in_version() {
+        let with_version: &[u8] = b"H\tVN:Z:1.0
+S\t1\tACGT
+W\tsample\t1\tchr1\t*\t*\t>1
+";
+        let mixed: &[u8] = b"S\t1\tACGT
+S\t2\tACGT
+L\t1\t+\t2\t+\t0M
+E\t*\t1+\t2+\t0\t4$\t0\t4$\t4M
+";
+        let parser: GFAParser<Vec<u8>, ()> = GFAParser::new();
+        for &input in [with_version, mixed].iter() {
+            let gfa = parser.parse_lines(ByteSlice::lines(input)).unwrap();
+            assert_eq!(gfa.walks.len() + gfa.edges.len(), 1);
+        }
+
+        let parser: GFAParser<Vec<u8>, ()> =
+            GFAParserBuilder::all().pedantic_errors().build();
+        for &(input, version, line) in [
+            (with_version, GFAVersion::V1_0, 3),
+            (mixed, GFAVersion::V1_0, 4),
+        ]
+        .iter()
+        {
+            let mut lines = parser.parse_reader(input);
+            let borrowed = std::iter::from_fn(|| {
+                lines.next_borrowed().map(|line| line.err())
+            });
+            let errors = [
+                parser.parse_lines(ByteSlice::lines(input)).unwrap_err(),
+                parser.parse_bytes_par(input).unwrap_err(),
+                parser.parse_reader(input).find_map(Result::err).unwrap(),
+                borrowed.flatten().next().unwrap(),
+            ];
+            for err in errors.iter() {
+                match err {
+                    ParseError::LineTypeNotInVersion(v, loc) => {
+                        assert_eq!(*v, version);
+                        assert_eq!(loc.line, Some(line));
+                    }
+                    _ => panic!("unexpected error: {}", err),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn error_actions_override_tolerance() {
+        let input: &[u8] = b"H\tVN:Z:1.0
+S\t1\tACGT
+# a comment
+S\tx\tGGTA
+S\t2\tGGTA
+L\t1\t+\t2\t?\t0M
+";
+        let mut builder = GFAParserBuilder::all();
+        builder
+            .ignore_errors()
+            .error_action(ErrorKind::Orientation, ErrorAction::Fail);
+        let parser: GFAParser<usize, ()> = builder.build();
+        let err = parser.parse_reader(input).find_map(Result::err).unwrap();
+        assert_eq!(err.kind(), Some(ErrorKind::Orientation));
+        assert_eq!(err.location().unwrap().line, Some(6));
+
+        let parser: GFAParser<usize, ()> = GFAParserBuilder::all()
+            .pedantic_errors()
+            .error_action(ErrorKind::UnknownLineType, ErrorAction::Ignore)
+            .error_action(ErrorKind::UintId, ErrorAction::Ignore)
+            .error_action(ErrorKind::Orientation, ErrorAction::Ignore)
+            .build();
+        let gfa = parser.parse_lines(ByteSlice::lines(input)).unwrap();
+        assert_eq!(gfa.segments.len(), 2);
+        assert!(gfa.links.is_empty());
+    }
+
+    #[test]
+    fn warnings_are_passed_to_callback() {
+        use std::sync::{Arc, Mutex};
+
+        let input: &[u8] = b"H\tVN:Z:1.0
+S\t1\tACGT
+# a comment
+S\tx\tGGTA
+L\t1\t+\t2\t?\t0M
+";
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let parser: GFAParser<usize, ()> = {
+            let warnings = warnings.clone();
+            GFAParserBuilder::all()
+                .error_action(ErrorKind::UintId, ErrorAction::Warn)
+                .error_action(ErrorKind::Orientation, ErrorAction::Warn)
+                .on_warning(move |err, loc| {
+                    warnings.lock().unwrap().push((err.kind(), loc.clone()))
+                })
+                .build()
+        };
+        let lines: Vec<_> = parser
+            .parse_reader(input)
+            .with_file_name("input.gfa")
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(lines.len(), 2);
+
+        let warnings = warnings.lock().unwrap();
+        let kinds: Vec<_> = warnings.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(
+            kinds,
+            vec![Some(ErrorKind::UintId), Some(ErrorKind::Orientation)]
+        );
+        let (_, loc) = &warnings[1];
+        assert_eq!(loc.file.as_deref(), Some("input.gfa"));
+        assert_eq!(loc.line, Some(5));
+        assert_eq!(loc.column, Some(5));
+    }
+
+    #[test]
+    fn invalid_optional_fields() {
+        use std::sync::{Arc, Mutex};
+
+        let parse = |field: &[u8]| match OptField::parse(field) {
+            Err(ParseFieldError::InvalidOptField(tag, reason)) => {
+                Some((tag.into_vec(), reason))
+            }
+            _ => None,
+        };
+        use OptFieldError as OFE;
+        let ln = b"LN".to_vec();
+        assert_eq!(
+            parse(b"LN:i:x"),
+            Some((ln.clone(), OFE::InvalidValue(b'i')))
+        );
+        assert_eq!(
+            parse(b"LN:q:5"),
+            Some((ln.clone(), OFE::UnknownType(b'q')))
+        );
+        assert_eq!(parse(b"LN:i"), Some((ln, OFE::InvalidFormat)));
+        assert_eq!(parse(b"1N:i:5"), Some((b"1N".to_vec(), OFE::InvalidTag)));
+        assert_eq!(
+            parse(b"BI:B:i1,x,3"),
+            Some((b"BI".to_vec(), OFE::InvalidArrayElement))
+        );
+        assert!(parse(b"LN:i:5").is_none());
+
+        let input: &[u8] = b"S\t1\tACGT\tLN:i:4\tRC:i:x\tKC:i:10";
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let parser: GFAParser<usize, OptionalFields> = {
+            let warnings = warnings.clone();
+            GFAParserBuilder::all()
+                .on_warning(move |err, loc| {
+                    warnings.lock().unwrap().push((err.kind(), loc.clone()))
+                })
+                .build()
+        };
+        let gfa = parser.parse_lines(std::iter::once(input)).unwrap();
+        let tags: Vec<_> = gfa.segments[0]
+            .optional
+            .iter()
+            .map(|field| field.tag)
+            .collect();
+        assert_eq!(tags, vec![*b"LN", *b"KC"]);
+
+        let warnings = warnings.lock().unwrap();
+        assert_eq!(warnings.len(), 1);
+        let (kind, loc) = &warnings[0];
+        assert_eq!(*kind, Some(ErrorKind::InvalidOptField));
+        assert_eq!(loc.line, Some(1));
+        assert_eq!(loc.column, Some(5));
+
+        let parser: GFAParser<usize, OptionalFields> =
+            GFAParserBuilder::all().pedantic_errors().build();
+        let err = parser.parse_lines(std::iter::once(input)).unwrap_err();
+        assert_eq!(err.kind(), Some(ErrorKind::InvalidOptField));
+        assert_eq!(err.location().unwrap().column, Some(5));
+
+        // Optional fields aren't parsed at all without a container
+        let parser: GFAParser<usize, ()> =
+            GFAParserBuilder::all().pedantic_errors().build();
+        assert!(parser.parse_lines(std::iter::once(input)).is_ok());
+    }
+
+    #[test]
+    fn pedantic_reserved_tag_types() {
+        use std::sync::{Arc, Mutex};
+
+        let line = b"S\t1\tACGT\tLN:Z:four\tRC:i:3";
+
+        let parser: GFAParser<usize, OptionalFields> = GFAParser::new();
+        let segment = parser.parse_gfa_line(line).unwrap().some_segment();
+        let segment = segment.unwrap();
+        assert_eq!(segment.optional.len(), 2);
+        assert_eq!(segment.length_tag(), None);
+        assert_eq!(segment.read_count(), Some(3));
+
+        let parser: GFAParser<usize, OptionalFields> =
+            GFAParserBuilder::all().pedantic_errors().build();
+        let err = parser.parse_gfa_line(line).unwrap_err();
+        assert_eq!(err.kind(), Some(ErrorKind::ReservedTagType));
+        assert_eq!(err.location().and_then(|l| l.column), Some(4));
+        assert!(parser.parse_gfa_line(b"S\t1\tACGT\tLN:i:4").is_ok());
+
+        // Warned about, but kept
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let parser: GFAParser<usize, OptionalFields> = {
+            let warnings = warnings.clone();
+            GFAParserBuilder::all()
+                .error_action(ErrorKind::ReservedTagType, ErrorAction::Warn)
+                .on_warning(move |err, _| {
+                    warnings.lock().unwrap().push(err.kind())
+                })
+                .build()
+        };
+        let segment = parser.parse_gfa_line(line).unwrap().some_segment();
+        assert_eq!(segment.unwrap().optional.len(), 2);
+        assert_eq!(
+            *warnings.lock().unwrap(),
+            vec![Some(ErrorKind::ReservedTagType)]
+        );
+    }
+
+    #[test]
+    fn only_parse_optional_tags() {
+        let input: &[u8] = b"H\tVN:Z:1.0\tSO:Z:coordinate
+S\t1\tACGT\tLN:i:4\tKC:i:x\tRC:i:10
+S\t2\tGG\tXY:Z:skipped
+";
+        let parser: GFAParser<usize, OptionalFields> = GFAParserBuilder::all()
+            .pedantic_errors()
+            .optional_tags(&["LN", "RC", "SO"])
+            .build();
+        let gfa = parser.parse_lines(ByteSlice::lines(input)).unwrap();
+
+        let tags = |fields: &OptionalFields| -> Vec<[u8; 2]> {
+            fields.iter().map(|field| field.tag).collect()
+        };
+        assert_eq!(tags(&gfa.header.optional), vec![*b"SO"]);
+        assert_eq!(tags(&gfa.segments[0].optional), vec![*b"LN", *b"RC"]);
+        assert!(gfa.segments[1].optional.is_empty());
+        assert_eq!(gfa.header.version.as_deref(), Some(&b"1.0"[..]));
+    }
+
+    #[test]
+    fn can_parse_reader() {
+        let input: &[u8] = b"H\tVN:Z:1.0
+# a comment
+S\t1\tACGT
+L\t1\t+\t2\t-\t0M
+
+S\t2\tGGTA
+P\t14\t1+,2-\t0M
+";
+
+        let parser: GFAParser<usize, ()> =
+            GFAParserBuilder::all().segments(false).build();
+        let lines = parser
+            .parse_reader(input)
+            .collect::<GFAResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(lines[0], Line::Header(_)));
+        assert!(matches!(lines[1], Line::Link(_)));
+        assert!(matches!(lines[2], Line::Path(_)));
+
+        // The comment and the empty line are skipped even when
+        // pedantic, unlike lines with an unknown line type
+        let parser: GFAParser<usize, ()> =
+            GFAParserBuilder::all().pedantic_errors().build();
+        let lines = parser
+            .parse_reader(input)
+            .collect::<GFAResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(lines.len(), 5);
+        let gfa = parser.parse_lines(ByteSlice::lines(input)).unwrap();
+        assert_eq!(gfa.segments.len(), 2);
+        let err = parser
+            .parse_lines(ByteSlice::lines(&b"X\tunknown"[..]))
+            .unwrap_err();
+        assert!(matches!(err, ParseError::UnknownLineType(_)));
+
+        let parser: GFAParser<usize, ()> =
+            GFAParserBuilder::all().segments(false).build();
+        let gfa = parser.parse_lines(ByteSlice::lines(input)).unwrap();
+        assert!(gfa.segments.is_empty());
+        assert_eq!(gfa.links.len(), 1);
+        assert_eq!(gfa.paths.len(), 1);
     }
 
     #[test]
@@ -618,20 +2832,17 @@ mod tests {
 
         let optional_fields: Vec<_> = vec![
             OptField::new(b"LN", Int(123)),
-            OptField::new(
-                b"SH",
-                H(vec![0xA, 0xA, 0xC, 0xC, 0xF, 0xF, 0x0, 0x5]),
-            ),
+            OptField::new(b"SH", H(vec![0xAA, 0xCC, 0xFF, 0x05])),
             OptField::new(b"RC", Int(123)),
             OptField::new(b"UR", Z(Vec::<u8>::from("http://test.com/"))),
             OptField::new(b"IJ", A(b'x')),
-            OptField::new(b"AB", BInt(vec![1, 2, 3, 52124])),
+            OptField::new(b"AB", BInt(IntArrayType::U32, vec![1, 2, 3, 52124])),
         ]
         .into_iter()
         .collect();
 
         let segment_1: GFAFieldResult<Segment<Vec<u8>, ()>> =
-            Segment::parse_line(fields.clone());
+            Segment::parse_line(fields.clone(), &mut OptFieldParser::default());
 
         assert!(segment_1.is_ok());
         assert_eq!(
@@ -644,7 +2855,8 @@ mod tests {
         );
 
         let segment_2: Segment<Vec<u8>, OptionalFields> =
-            Segment::parse_line(fields).unwrap();
+            Segment::parse_line(fields, &mut OptFieldParser::default())
+                .unwrap();
 
         assert_eq!(segment_2.name.as_bstr(), name);
         assert_eq!(segment_2.sequence.as_bstr(), seq);
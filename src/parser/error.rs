@@ -1,20 +1,117 @@
-use std::{error, fmt};
+use std::{
+    error, fmt,
+    sync::{Arc, Mutex},
+};
 
 use bstr::ByteSlice;
 
+use crate::gfa::GFAVersion;
+
 pub type GFAFieldResult<T> = Result<T, ParseFieldError>;
 pub type GFAResult<T> = Result<T, ParseError>;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 pub enum ParserTolerance {
     IgnoreAll,
+    #[default]
     Safe,
     Pedantic,
 }
 
-impl Default for ParserTolerance {
-    fn default() -> Self {
-        Self::Safe
+impl ParserTolerance {
+    /// The action the tolerance takes for a kind of error, if the
+    /// action hasn't been set for that kind of error.
+    #[inline]
+    pub fn default_action(&self, kind: ErrorKind) -> ErrorAction {
+        use ParserTolerance as Tol;
+        match self {
+            Tol::IgnoreAll => ErrorAction::Ignore,
+            Tol::Safe => match kind {
+                ErrorKind::UnknownLineType
+                | ErrorKind::EmptyLine
+                | ErrorKind::LineTypeNotInVersion
+                | ErrorKind::ReservedTagType => ErrorAction::Ignore,
+                ErrorKind::VersionConflict | ErrorKind::InvalidOptField => {
+                    ErrorAction::Warn
+                }
+                _ => ErrorAction::Fail,
+            },
+            Tol::Pedantic => ErrorAction::Fail,
+        }
+    }
+}
+
+/// The kinds of errors that the parser can be configured to handle
+/// differently, corresponding to the variants of `ParseError` that
+/// are caused by a line, and of `ParseFieldError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    UnknownLineType,
+    EmptyLine,
+    VersionConflict,
+    LineTypeNotInVersion,
+    UintId,
+    Utf8,
+    ParseFromString,
+    Orientation,
+    InvalidField,
+    InvalidOptField,
+    ReservedTagType,
+    MissingFields,
+    Unknown,
+}
+
+/// What the parser does when a line fails to parse.
+///
+/// With `Ignore` or `Warn`, the parser continues past the error, and
+/// what's skipped depends on the kind of error:
+///
+/// * `InvalidOptField`: only the optional field is skipped, and the
+///   rest of the line is kept.
+/// * `ReservedTagType`: nothing is skipped, and the field is kept
+///   with its type.
+/// * `VersionConflict`: the line is kept, without its version.
+/// * `LineTypeNotInVersion`: the line is kept.
+/// * Any other kind: the line is skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// Continue past the error, as described above.
+    Ignore,
+    /// Continue past the error as with `Ignore`, and pass the error
+    /// to the parser's warning callback, if it has one.
+    Warn,
+    /// Stop parsing and return the error.
+    Fail,
+}
+
+/// Callback that the parser passes errors with the `Warn` action to,
+/// along with the location of the line. As the parser can be cloned
+/// and used from multiple threads, the callback is shared.
+#[derive(Clone)]
+pub struct WarningCallback(Arc<Mutex<WarningFn>>);
+
+type WarningFn = dyn FnMut(&ParseError, &Location) + Send;
+
+impl WarningCallback {
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: FnMut(&ParseError, &Location) + Send + 'static,
+    {
+        WarningCallback(Arc::new(Mutex::new(callback)))
+    }
+
+    #[inline]
+    pub fn warn(&self, error: &ParseError, location: &Location) {
+        // A panic in a previous call doesn't leave anything in a
+        // broken state, so it's fine to ignore the poisoning
+        let mut callback = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        callback(error, location)
+    }
+}
+
+impl fmt::Debug for WarningCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WarningCallback")
     }
 }
 
@@ -33,10 +130,71 @@ pub enum ParseFieldError {
     /// A required field was incorrectly formatted. Includes the field
     /// name as defined by the GFA1 spec.
     InvalidField(&'static str),
+    /// An optional field was incorrectly formatted. Includes the tag,
+    /// or whatever was in its place, and the reason.
+    InvalidOptField(Box<[u8]>, OptFieldError),
     MissingFields,
     Unknown,
 }
 
+/// The reasons an optional field can fail to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptFieldError {
+    /// The tag doesn't match [A-Za-z][A-Za-z0-9].
+    InvalidTag,
+    /// The field isn't in the <TAG>:<TYPE>:<VALUE> format.
+    InvalidFormat,
+    /// The type isn't one of the SAM types.
+    UnknownType(u8),
+    /// The value doesn't match the type of the field.
+    InvalidValue(u8),
+    /// The field has a different type than the one it's expected to
+    /// have, e.g. by a struct that derives `OptFields`.
+    UnexpectedType(u8),
+    /// The tag is reserved by the spec with another type.
+    ReservedTagType { expected: u8, found: u8 },
+    /// A `B` array is missing the type of its elements.
+    MissingArrayType,
+    /// The type of the elements of a `B` array isn't one of the SAM
+    /// array types.
+    UnknownArrayType(u8),
+    /// An element of a `B` array doesn't match the type of the array,
+    /// or is out of the range of the type.
+    InvalidArrayElement,
+}
+
+impl fmt::Display for OptFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use OptFieldError as OFE;
+        match self {
+            OFE::InvalidTag => write!(f, "invalid tag"),
+            OFE::InvalidFormat => {
+                write!(f, "expected the format <TAG>:<TYPE>:<VALUE>")
+            }
+            OFE::UnknownType(t) => {
+                write!(f, "unknown type `{}`", char::from(*t))
+            }
+            OFE::InvalidValue(t) => {
+                write!(f, "invalid value for type `{}`", char::from(*t))
+            }
+            OFE::UnexpectedType(t) => {
+                write!(f, "unexpected type `{}`", char::from(*t))
+            }
+            OFE::ReservedTagType { expected, found } => write!(
+                f,
+                "reserved tag has type `{}` instead of `{}`",
+                char::from(*found),
+                char::from(*expected)
+            ),
+            OFE::MissingArrayType => write!(f, "missing array type"),
+            OFE::UnknownArrayType(t) => {
+                write!(f, "unknown array type `{}`", char::from(*t))
+            }
+            OFE::InvalidArrayElement => write!(f, "invalid array element"),
+        }
+    }
+}
+
 macro_rules! impl_many_from {
     ($to:ty, ($from:ty, $out:expr)) => ();
     ($to:ty, ($from:ty, $out:expr), $(($f:ty, $o:expr)),* $(,)?) => (
@@ -63,6 +221,26 @@ impl_many_from!(
     )
 );
 
+impl ParseFieldError {
+    #[inline]
+    pub fn kind(&self) -> ErrorKind {
+        use ParseFieldError as PFE;
+        match self {
+            PFE::UintIdError => ErrorKind::UintId,
+            PFE::Utf8Error => ErrorKind::Utf8,
+            PFE::ParseFromStringError => ErrorKind::ParseFromString,
+            PFE::OrientationError => ErrorKind::Orientation,
+            PFE::InvalidField(_) => ErrorKind::InvalidField,
+            PFE::InvalidOptField(_, OptFieldError::ReservedTagType { .. }) => {
+                ErrorKind::ReservedTagType
+            }
+            PFE::InvalidOptField(..) => ErrorKind::InvalidOptField,
+            PFE::MissingFields => ErrorKind::MissingFields,
+            PFE::Unknown => ErrorKind::Unknown,
+        }
+    }
+}
+
 impl fmt::Display for ParseFieldError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use ParseFieldError as PFE;
@@ -82,6 +260,12 @@ impl fmt::Display for ParseFieldError {
             PFE::InvalidField(field) => {
                 write!(f, "Failed to parse field `{}`", field)
             }
+            PFE::InvalidOptField(tag, reason) => write!(
+                f,
+                "Failed to parse optional field `{}`: {}",
+                tag.as_bstr(),
+                reason
+            ),
             PFE::MissingFields => write!(f, "Line is missing required fields"),
             PFE::Unknown => write!(f, "Unknown error when parsing a field"),
         }
@@ -90,18 +274,94 @@ impl fmt::Display for ParseFieldError {
 
 impl error::Error for ParseFieldError {}
 
+/// The location of a line, or of a field in a line, in a GFA file.
+/// Line and column numbers start at 1, where the column is the index
+/// of the tab-separated field, including the line type. The byte
+/// offset is the offset of the start of the line. Parts of the
+/// location can be missing, e.g. when parsing a single line, the
+/// line number and offset are unknown.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub offset: Option<u64>,
+    pub column: Option<usize>,
+}
+
+impl Location {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.file.is_none()
+            && self.line.is_none()
+            && self.offset.is_none()
+            && self.column.is_none()
+    }
+}
+
+/// Display produces the location as `file.gfa:12034:3`, leaving out
+/// the parts that are missing. Without a line number, the byte offset
+/// of the line is used instead, as in `file.gfa:byte 5120:3`.
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = self.line.map(|l| l.to_string());
+        let parts = [
+            line.or_else(|| self.offset.map(|o| format!("byte {}", o))),
+            self.column.map(|c| c.to_string()),
+        ];
+        let mut sep = "";
+        if let Some(file) = &self.file {
+            write!(f, "{}", file)?;
+            sep = ":";
+        }
+        for part in parts.iter().flatten() {
+            write!(f, "{}{}", sep, part)?;
+            sep = ":";
+        }
+        Ok(())
+    }
+}
+
+/// A line that caused an error when parsing a file while collecting
+/// errors, along with the error. The line is skipped, unless the
+/// action of the error keeps it, e.g. when an optional field is
+/// skipped with a warning.
+#[derive(Debug)]
+pub struct SkippedLine {
+    pub error: ParseError,
+    pub location: Location,
+}
+
+/// Display produces the error, which is prefixed by the location.
+impl fmt::Display for SkippedLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error.location() {
+            Some(_) => write!(f, "{}", self.error),
+            None => write!(f, "{}: {}", self.location, self.error),
+        }
+    }
+}
+
 /// Type encapsulating different kinds of GFA parsing errors
 #[derive(Debug)]
 pub enum ParseError {
-    /// The line type was something other than 'H', 'S', 'L', 'C', or
-    /// 'P'. This is ignored by the file parser rather than a fail
-    /// condition.
-    UnknownLineType,
+    /// The line type was not one of the GFA1 ('H', 'S', 'L', 'C',
+    /// 'P') or GFA2 ('E', 'F', 'G', 'O', 'U') line types. This is
+    /// ignored by the file parser rather than a fail condition.
+    UnknownLineType(Location),
     /// Tried to parse an empty line. Can be ignored.
-    EmptyLine,
-    /// A line couldn't be parsed. Includes the problem line and a
-    /// variant describing the error.
-    InvalidLine(ParseFieldError, String),
+    EmptyLine(Location),
+    /// A line couldn't be parsed. Includes the problem line, a
+    /// variant describing the error, and the location of the field
+    /// that caused the error.
+    InvalidLine(ParseFieldError, String, Location),
+    /// A header line has a different version than an earlier header
+    /// line. Includes the first version, and the version of the line.
+    /// The first version is kept if the error is ignored.
+    VersionConflict(Vec<u8>, Vec<u8>, Location),
+    /// The line type isn't part of the version of the GFA, which is
+    /// either the version in the header, or detected from the lines
+    /// before it.
+    LineTypeNotInVersion(GFAVersion, Location),
     /// A field couldn't be parsed
     InvalidField(ParseFieldError),
     /// Wrapper for an IO error.
@@ -112,14 +372,26 @@ pub enum ParseError {
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use ParseError as PE;
+        if let Some(loc) = self.location().filter(|loc| !loc.is_empty()) {
+            write!(f, "{}: ", loc)?;
+        }
         match self {
-            PE::UnknownLineType => {
-                write!(f, "Line type was not one of 'H', 'S', 'L', 'C', 'P'")
+            PE::UnknownLineType(_) => {
+                write!(f, "Line type was not a known GFA line type")
             }
-            PE::EmptyLine => write!(f, "Line was empty"),
-            PE::InvalidLine(field_err, line) => {
+            PE::EmptyLine(_) => write!(f, "Line was empty"),
+            PE::InvalidLine(field_err, line, _) => {
                 write!(f, "Failed to parse line {}, error: {}", line, field_err)
             }
+            PE::VersionConflict(first, version, _) => write!(
+                f,
+                "Header version {} conflicts with earlier version {}",
+                version.as_bstr(),
+                first.as_bstr()
+            ),
+            PE::LineTypeNotInVersion(version, _) => {
+                write!(f, "Line type is not allowed in GFA {}", version)
+            }
             PE::InvalidField(field_err) => {
                 write!(f, "Failed to parse field: {}", field_err)
             }
@@ -147,23 +419,87 @@ impl error::Error for ParseError {}
 
 impl ParseError {
     #[inline]
-    pub(crate) fn invalid_line(error: ParseFieldError, line: &[u8]) -> Self {
+    pub(crate) fn invalid_line(
+        error: ParseFieldError,
+        line: &[u8],
+        column: usize,
+    ) -> Self {
         let mut dest = String::new();
         line.to_str_lossy_into(&mut dest);
-        Self::InvalidLine(error, dest)
+        let location = Location {
+            column: Some(column),
+            ..Location::default()
+        };
+        Self::InvalidLine(error, dest, location)
+    }
+
+    /// The location of the line that caused the error, if the error
+    /// was caused by a line.
+    #[inline]
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            ParseError::UnknownLineType(loc)
+            | ParseError::EmptyLine(loc)
+            | ParseError::VersionConflict(_, _, loc)
+            | ParseError::LineTypeNotInVersion(_, loc)
+            | ParseError::InvalidLine(_, _, loc) => Some(loc),
+            _ => None,
+        }
+    }
+
+    /// The kind of the error, which decides the action the parser
+    /// takes. IO errors don't have a kind, as they always stop the
+    /// parser.
+    #[inline]
+    pub fn kind(&self) -> Option<ErrorKind> {
+        match self {
+            ParseError::UnknownLineType(_) => Some(ErrorKind::UnknownLineType),
+            ParseError::EmptyLine(_) => Some(ErrorKind::EmptyLine),
+            ParseError::VersionConflict(..) => Some(ErrorKind::VersionConflict),
+            ParseError::LineTypeNotInVersion(..) => {
+                Some(ErrorKind::LineTypeNotInVersion)
+            }
+            ParseError::InvalidLine(err, _, _) => Some(err.kind()),
+            ParseError::InvalidField(err) => Some(err.kind()),
+            ParseError::IOError(_) => None,
+            ParseError::Unknown => Some(ErrorKind::Unknown),
+        }
+    }
+
+    /// Set the file, line and byte offset of the error's location to
+    /// those of the line that caused it, keeping the column.
+    #[inline]
+    pub(crate) fn at_line(self, line: &Location) -> Self {
+        self.map_location(|loc| {
+            loc.file.clone_from(&line.file);
+            loc.line = line.line;
+            loc.offset = line.offset;
+        })
+    }
+
+    /// Update the location of the error, if it has one.
+    #[inline]
+    pub(crate) fn map_location<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut Location),
+    {
+        match &mut self {
+            ParseError::UnknownLineType(loc)
+            | ParseError::EmptyLine(loc)
+            | ParseError::VersionConflict(_, _, loc)
+            | ParseError::LineTypeNotInVersion(_, loc)
+            | ParseError::InvalidLine(_, _, loc) => f(loc),
+            _ => (),
+        }
+        self
     }
 
     #[inline]
     pub fn can_safely_continue(&self, tol: &ParserTolerance) -> bool {
-        use ParserTolerance as Tol;
-        match tol {
-            Tol::IgnoreAll => true,
-            Tol::Safe => match self {
-                ParseError::EmptyLine => true,
-                ParseError::UnknownLineType => true,
-                _ => false,
-            },
-            Tol::Pedantic => false,
+        match self.kind() {
+            Some(kind) => tol.default_action(kind) != ErrorAction::Fail,
+            // IO errors always stop the parser
+            None => matches!(tol, ParserTolerance::IgnoreAll),
         }
     }
 }
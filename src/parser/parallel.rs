@@ -0,0 +1,331 @@
+//! Parallel parsing of GFA files that are already in memory.
+//!
+//! The input is split into chunks at line boundaries, which are
+//! parsed on the rayon thread pool, after which the lines are
+//! inserted into the GFA in the same order as in the input. The
+//! number of threads used can be controlled by running the parser
+//! inside a custom rayon `ThreadPool`.
+//!
+//! Errors with the `Warn` action are passed to the warning callback
+//! as the chunks are parsed, so they may not be in input order.
+
+use bstr::ByteSlice;
+use memmap::Mmap;
+use rayon::prelude::*;
+
+use std::{fs::File, io::Read};
+
+use crate::{
+    compression::is_gzip,
+    gfa::{GFAVersion, Line, SegmentId, GFA},
+    optfields::OptFields,
+};
+
+use super::{GFAParser, GFAResult, Location, VersionState};
+
+// Chunks smaller than this aren't worth the overhead of parsing
+// them in parallel
+const MIN_CHUNK_SIZE: usize = 1 << 16;
+// The number of chunks to create per thread, so that threads that
+// finish early can pick up more work
+const CHUNKS_PER_THREAD: usize = 4;
+
+// The parsed lines of a chunk, and the locations of its header lines
+type ParsedChunk<N, T> = (Vec<Line<N, T>>, Vec<Location>);
+
+/// What's known about the version of the GFA before it's split into
+/// chunks, so that the lines of each chunk are parsed and checked as
+/// they would be when parsing sequentially.
+#[derive(Debug, Default, Clone, Copy)]
+struct InputVersion {
+    // The offset of the first header line with a version, and the
+    // version, if it's a known version
+    header: Option<(u64, Option<GFAVersion>)>,
+    // The version of the first line that decides the version, if
+    // there's no known header version before it
+    first_line: Option<GFAVersion>,
+}
+
+impl InputVersion {
+    /// The version given by the header at the line with the offset.
+    fn at(&self, offset: u64) -> Option<GFAVersion> {
+        self.header
+            .filter(|&(header_offset, _)| offset > header_offset)
+            .and_then(|(_, version)| version)
+    }
+}
+
+/// Split the bytes into chunks of roughly `chunk_size` bytes, each
+/// ending at a newline or the end of the input.
+fn split_chunks(bytes: &[u8], chunk_size: usize) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let end = rest
+            .get(chunk_size..)
+            .and_then(|tail| tail.find_byte(b'\n'))
+            .map_or(rest.len(), |ix| chunk_size + ix + 1);
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+impl<N, T> GFAParser<N, T>
+where
+    N: SegmentId + Send + Sync,
+    T: OptFields + Send + Sync,
+{
+    /// Find the version of the input, as far as the chunks need it.
+    fn scan_version(&self, bytes: &[u8]) -> InputVersion {
+        let line_at = |start: usize| {
+            let rest = &bytes[start..];
+            rest.find_byte(b'\n').map_or(rest, |end| &rest[..end])
+        };
+        let mut header_starts = Some(0)
+            .filter(|_| bytes.starts_with(b"H"))
+            .into_iter()
+            .chain(bytes.find_iter("\nH").map(|ix| ix + 1));
+
+        // Skipped optional fields are warned about when the lines are
+        // parsed again as part of their chunks
+        let header = header_starts.find_map(|start| {
+            let line = line_at(start);
+            match self.parse_gfa_line_skipping(line, None, &mut Vec::new()) {
+                Ok(Line::Header(header)) => {
+                    let version = header.version?;
+                    Some((start as u64, GFAVersion::from_bytes(&version)))
+                }
+                _ => None,
+            }
+        });
+        let mut version = InputVersion {
+            header,
+            first_line: None,
+        };
+
+        let mut offset = 0;
+        for line in bytes.lines_with_terminator() {
+            let line_offset = offset;
+            offset += line.len() as u64;
+            // Every following line also has a version from the header
+            if version.at(line_offset).is_some() {
+                break;
+            }
+            let parsed =
+                self.parse_line_if_included(line, None, &mut Vec::new());
+            if let Some(Ok(line)) = parsed {
+                version.first_line = GFAVersion::introducing(line.kind());
+                if version.first_line.is_some() {
+                    break;
+                }
+            }
+        }
+
+        version
+    }
+
+    /// Parse the lines of a chunk, where `start` is the location of
+    /// the first line of the chunk in the input. Also returns the
+    /// locations of the header lines, which are needed to report
+    /// conflicting versions once the chunks have been parsed.
+    fn parse_chunk(
+        &self,
+        chunk: &[u8],
+        start: &Location,
+        input_version: InputVersion,
+    ) -> GFAResult<ParsedChunk<N, T>> {
+        let mut offset = start.offset.unwrap_or(0);
+        let first_line = start.line.unwrap_or(1);
+        let mut lines = Vec::new();
+        let mut headers = Vec::new();
+        let mut version = VersionState {
+            detected: input_version.first_line,
+            ..VersionState::default()
+        };
+
+        for (ix, line) in chunk.lines_with_terminator().enumerate() {
+            let line_offset = offset;
+            offset += line.len() as u64;
+            let location = || Location {
+                file: start.file.clone(),
+                line: Some(first_line + ix),
+                offset: Some(line_offset),
+                column: None,
+            };
+            version.version = input_version.at(line_offset);
+            let parsed =
+                self.parse_line_tolerant(line, version.version, location);
+            if let Some(parsed) = parsed {
+                let parsed = parsed?;
+                // Headers are checked once all chunks have been parsed,
+                // as they're compared to the earlier headers
+                if let Line::Header(_) = parsed {
+                    headers.push(location());
+                    lines.push(parsed);
+                } else {
+                    lines.push(self.check_line(
+                        parsed,
+                        &mut version,
+                        location,
+                    )?);
+                }
+            }
+        }
+
+        Ok((lines, headers))
+    }
+
+    /// Parse a GFA from a slice of bytes using multiple threads. The
+    /// result is the same as that of `parse_lines` over the lines of
+    /// `bytes`, including which error is returned if more than one
+    /// line fails to parse.
+    pub fn parse_bytes_par(&self, bytes: &[u8]) -> GFAResult<GFA<N, T>> {
+        self.parse_bytes_par_in(bytes, None)
+    }
+
+    /// Parse the bytes in parallel, with the file name, if any, used
+    /// in the locations of errors.
+    fn parse_bytes_par_in(
+        &self,
+        bytes: &[u8],
+        file: Option<String>,
+    ) -> GFAResult<GFA<N, T>> {
+        let chunk_count = rayon::current_num_threads() * CHUNKS_PER_THREAD;
+        let chunk_size = (bytes.len() / chunk_count).max(MIN_CHUNK_SIZE);
+        let chunks = split_chunks(bytes, chunk_size);
+
+        // The location of the first line of each chunk, so that errors
+        // and warnings can be located within the whole input
+        let newlines: Vec<usize> = chunks
+            .par_iter()
+            .map(|chunk| chunk.find_iter("\n").count())
+            .collect();
+        let mut starts = Vec::with_capacity(chunks.len());
+        let (mut line, mut offset) = (1, 0);
+        for (chunk, count) in chunks.iter().zip(newlines) {
+            starts.push(Location {
+                file: file.clone(),
+                line: Some(line),
+                offset: Some(offset),
+                column: None,
+            });
+            line += count;
+            offset += chunk.len() as u64;
+        }
+
+        let input_version = self.scan_version(bytes);
+        let parsed: Vec<_> = chunks
+            .par_iter()
+            .zip(starts.par_iter())
+            .map(|(chunk, start)| self.parse_chunk(chunk, start, input_version))
+            .collect();
+
+        let mut gfa = self.new_gfa();
+        let mut version = VersionState::default();
+        for parsed in parsed {
+            let (lines, headers) = parsed?;
+            let mut headers = headers.into_iter();
+            for line in lines {
+                let line = match line {
+                    Line::Header(_) => {
+                        let location = headers.next().unwrap_or_default();
+                        self.check_line(line, &mut version, || location)?
+                    }
+                    line => line,
+                };
+                gfa.insert_line(line);
+            }
+        }
+
+        Ok(gfa)
+    }
+
+    /// Parse a GFA file using multiple threads. Uncompressed files
+    /// are memory-mapped, while gzip and BGZF compressed files are
+    /// decompressed into memory before parsing.
+    pub fn parse_file_par<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> GFAResult<GFA<N, T>> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        // Empty files can't be memory-mapped
+        if file.metadata()?.len() == 0 {
+            return Ok(self.new_gfa());
+        }
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let file_name = Some(path.display().to_string());
+        if is_gzip(&mmap) {
+            let mut bytes = Vec::new();
+            flate2::bufread::MultiGzDecoder::new(&mmap[..])
+                .read_to_end(&mut bytes)?;
+            self.parse_bytes_par_in(&bytes, file_name)
+        } else {
+            self.parse_bytes_par_in(&mmap, file_name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{optfields::OptionalFields, parser::ParseError};
+
+    #[test]
+    fn chunks_end_at_newlines() {
+        let bytes = b"S\t1\tA\nS\t2\tAC\nS\t3\tACG\nS\t4\tACGT";
+        let chunks = split_chunks(bytes, 4);
+        assert_eq!(
+            chunks,
+            vec![
+                &b"S\t1\tA\n"[..],
+                b"S\t2\tAC\n",
+                b"S\t3\tACG\n",
+                b"S\t4\tACGT"
+            ]
+        );
+        assert_eq!(split_chunks(bytes, 100), vec![&bytes[..]]);
+        assert!(split_chunks(b"", 4).is_empty());
+    }
+
+    #[test]
+    fn parallel_parse_matches_sequential() {
+        let parser: GFAParser<Vec<u8>, OptionalFields> = GFAParser::new();
+        let path = "./test/gfas/diatom.gfa";
+        let sequential = parser.parse_file(path).unwrap();
+        let parallel = parser.parse_file_par(path).unwrap();
+        assert_eq!(sequential, parallel);
+
+        let parser: GFAParser<usize, OptionalFields> = GFAParser::new();
+        let parallel = parser.parse_file_par("./test/gfas/lil.bgzf.gfa.gz");
+        assert_eq!(
+            parallel.unwrap(),
+            parser.parse_file("./test/gfas/lil.gfa").unwrap()
+        );
+
+        let parallel = parser.parse_file_par(path);
+        assert!(matches!(parallel, Err(ParseError::InvalidLine(..))));
+    }
+
+    #[test]
+    fn parallel_errors_include_location() {
+        // Enough lines for several chunks, with an error near the end
+        let mut bytes = Vec::new();
+        for i in 0..20000 {
+            bytes.extend(format!("S\t{}\tACGT\n", i).bytes());
+        }
+        let offset = bytes.len() as u64;
+        bytes.extend(b"L\t1\t+\t2\t?\t0M\n");
+        assert!(split_chunks(&bytes, MIN_CHUNK_SIZE).len() > 1);
+
+        let parser: GFAParser<usize, ()> = GFAParser::new();
+        let err = parser.parse_bytes_par(&bytes).unwrap_err();
+        let loc = err.location().unwrap();
+        assert_eq!(loc.line, Some(20001));
+        assert_eq!(loc.offset, Some(offset));
+        assert_eq!(loc.column, Some(5));
+    }
+}
@@ -0,0 +1,231 @@
+//! Hand-written scanners for the fields of GFA lines.
+//!
+//! Each scanner finds the same match as the regular expression from
+//! the GFA spec given in its documentation. Like `Regex::find`, this
+//! is the leftmost match in the input, which doesn't have to start at
+//! the beginning of the input, or cover all of it.
+
+use bstr::ByteSlice;
+
+/// Iterator over the tab-separated fields of a line.
+#[derive(Debug, Clone)]
+pub(crate) struct Fields<'a> {
+    rest: Option<&'a [u8]>,
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a [u8];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?;
+        match rest.find_byte(b'\t') {
+            Some(ix) => {
+                self.rest = Some(&rest[ix + 1..]);
+                Some(&rest[..ix])
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
+        }
+    }
+}
+
+/// Split a line on tabs, which produces the same fields as
+/// `line.split_str(b"\t")`.
+#[inline]
+pub(crate) fn split_tabs(line: &[u8]) -> Fields<'_> {
+    Fields { rest: Some(line) }
+}
+
+#[inline]
+fn is_printable(b: u8) -> bool {
+    matches!(b, b'!'..=b'~')
+}
+
+#[inline]
+fn is_sign(b: u8) -> bool {
+    b == b'-' || b == b'+'
+}
+
+#[inline]
+fn is_sequence_byte(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'=' || b == b'.'
+}
+
+/// The length of the run of bytes matching `pred` at the start of
+/// `bytes`.
+#[inline]
+fn run_len(bytes: &[u8], pred: impl Fn(u8) -> bool) -> usize {
+    bytes.iter().position(|&b| !pred(b)).unwrap_or(bytes.len())
+}
+
+/// Find the first run of at least one byte matching `pred`.
+#[inline]
+fn find_run(input: &[u8], pred: impl Fn(u8) -> bool) -> Option<&[u8]> {
+    let start = input.iter().position(|&b| pred(b))?;
+    let end = start + run_len(&input[start..], pred);
+    Some(&input[start..end])
+}
+
+/// `[!-)+-<>-~][!-~]*`, a segment name, which can't start with `*`
+/// or `=`.
+#[inline]
+pub(crate) fn find_segment_name(input: &[u8]) -> Option<&[u8]> {
+    let start = input
+        .iter()
+        .position(|&b| is_printable(b) && b != b'*' && b != b'=')?;
+    let end = start + 1 + run_len(&input[start + 1..], is_printable);
+    Some(&input[start..end])
+}
+
+/// `\*|[A-Za-z=.]+`, a sequence, or `*` if the sequence is missing.
+#[inline]
+pub(crate) fn find_sequence(input: &[u8]) -> Option<&[u8]> {
+    let start = input
+        .iter()
+        .position(|&b| b == b'*' || is_sequence_byte(b))?;
+    if input[start] == b'*' {
+        return Some(&input[start..=start]);
+    }
+    let end = start + run_len(&input[start..], is_sequence_byte);
+    Some(&input[start..end])
+}
+
+/// `[!-~]`
+#[inline]
+pub(crate) fn find_char(input: &[u8]) -> Option<u8> {
+    input.iter().copied().find(|&b| is_printable(b))
+}
+
+/// `[ !-~]+`
+#[inline]
+pub(crate) fn find_string(input: &[u8]) -> Option<&[u8]> {
+    find_run(input, |b| b == b' ' || is_printable(b))
+}
+
+/// `[0-9A-F]+`
+#[inline]
+pub(crate) fn find_hex(input: &[u8]) -> Option<&[u8]> {
+    find_run(input, |b| b.is_ascii_digit() || matches!(b, b'A'..=b'F'))
+}
+
+/// `[-+]?[0-9]+`
+#[inline]
+pub(crate) fn find_int(input: &[u8]) -> Option<&[u8]> {
+    (0..input.len()).find_map(|start| {
+        let digits_start = start + usize::from(is_sign(input[start]));
+        let digits = run_len(&input[digits_start..], |b| b.is_ascii_digit());
+        Some(&input[start..digits_start + digits]).filter(|_| digits > 0)
+    })
+}
+
+/// `[-+]?[0-9]*\.?[0-9]+([eE][-+]?[0-9]+)?`
+#[inline]
+pub(crate) fn find_float(input: &[u8]) -> Option<&[u8]> {
+    (0..input.len()).find_map(|start| {
+        let unsigned = start + usize::from(is_sign(input[start]));
+        let len = unsigned_float_len(&input[unsigned..])?;
+        Some(&input[start..unsigned + len])
+    })
+}
+
+/// The length of the float without a sign at the start of `bytes`.
+/// The regex prefers a fractional part, and falls back to an integer
+/// if there's no digit after the decimal point.
+fn unsigned_float_len(bytes: &[u8]) -> Option<usize> {
+    let is_digit = |b: u8| b.is_ascii_digit();
+    let int = run_len(bytes, is_digit);
+    let frac = match bytes.get(int) {
+        Some(b'.') => run_len(&bytes[int + 1..], is_digit),
+        _ => 0,
+    };
+
+    let mut len = if frac > 0 {
+        int + 1 + frac
+    } else if int > 0 {
+        int
+    } else {
+        return None;
+    };
+
+    if let Some(b'e') | Some(b'E') = bytes.get(len) {
+        let exp_sign = bytes.get(len + 1).is_some_and(|&b| is_sign(b));
+        let exp_start = len + 1 + usize::from(exp_sign);
+        let exp = run_len(&bytes[exp_start..], is_digit);
+        if exp > 0 {
+            len = exp_start + exp;
+        }
+    }
+
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::bytes::Regex;
+
+    // Every string of up to `max_len` bytes from the alphabet
+    fn all_strings(alphabet: &[u8], max_len: usize) -> Vec<Vec<u8>> {
+        let mut strings = vec![Vec::new()];
+        let mut last = vec![Vec::new()];
+        for _ in 0..max_len {
+            last = last
+                .iter()
+                .flat_map(|s: &Vec<u8>| {
+                    alphabet.iter().map(move |&b| {
+                        let mut s = s.clone();
+                        s.push(b);
+                        s
+                    })
+                })
+                .collect();
+            strings.extend(last.iter().cloned());
+        }
+        strings
+    }
+
+    fn assert_same<F>(regex: &str, alphabet: &[u8], scan: F)
+    where
+        F: Fn(&[u8]) -> Option<&[u8]>,
+    {
+        let regex = Regex::new(regex).unwrap();
+        for input in all_strings(alphabet, 5) {
+            let expected = regex.find(&input).map(|m| m.as_bytes());
+            assert_eq!(scan(&input), expected, "input: {:?}", input.as_bstr());
+        }
+    }
+
+    #[test]
+    fn scanners_match_regexes() {
+        assert_same(r"(?-u)[!-)+-<>-~][!-~]*", b"a*=\t ~\x7f", |s| {
+            find_segment_name(s)
+        });
+        assert_same(r"(?-u)\*|[A-Za-z=.]+", b"A*=.1 ", find_sequence);
+        assert_same(r"(?-u)[ !-~]+", b"a \t~\x7f", find_string);
+        assert_same(r"(?-u)[0-9A-F]+", b"0FGa ", find_hex);
+        assert_same(r"(?-u)[-+]?[0-9]+", b"1-+a ", find_int);
+        assert_same(
+            r"(?-u)[-+]?[0-9]*\.?[0-9]+([eE][-+]?[0-9]+)?",
+            b"1.eE-+a",
+            find_float,
+        );
+
+        let regex = Regex::new(r"(?-u)[!-~]").unwrap();
+        for input in all_strings(b"a \t~\x7f", 4) {
+            let expected = regex.find(&input).map(|m| m.as_bytes()[0]);
+            assert_eq!(find_char(&input), expected);
+        }
+    }
+
+    #[test]
+    fn split_on_tabs() {
+        for line in all_strings(b"a\t", 5) {
+            let fields: Vec<_> = split_tabs(&line).collect();
+            let expected: Vec<_> = line.split_str(b"\t").collect();
+            assert_eq!(fields, expected);
+        }
+    }
+}
@@ -1,109 +1,580 @@
+//! Functions for writing GFA files and lines. The lines are written
+//! to a `std::io::Write`, and `write_gfa` is a wrapper for writing a
+//! GFA to a `std::fmt::Write`, such as a `String`.
+
 use crate::{gfa::*, optfields::*};
 
 use bstr::ByteSlice;
-use std::fmt::Write;
-
-/// This entire module will probably be removed, with the functions
-/// replaced by Display implementations on GFA and the GFA line types,
-/// but I haven't gotten around to it yet
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+};
 
-fn write_optional_fields<U: OptFields, T: Write>(opts: &U, stream: &mut T) {
-    for field in opts.fields() {
-        write!(stream, "\t{}", field).unwrap_or_else(|err| {
-            panic!(
-                "Error writing optional field '{:?}' to stream, {:?}",
-                field, err
-            )
-        })
+fn write_optional_fields<U: OptFields, T: Write>(
+    opts: &U,
+    stream: &mut T,
+) -> io::Result<()> {
+    for field in opts.fields().iter() {
+        match &field.value {
+            // Written as bytes, as Display would replace bytes that
+            // aren't valid UTF-8
+            OptFieldVal::Z(x) => {
+                let [a, b] = field.tag;
+                write!(stream, "\t{}{}:Z:", char::from(a), char::from(b))?;
+                stream.write_all(x)?;
+            }
+            _ => write!(stream, "\t{}", field)?,
+        }
     }
+    Ok(())
 }
 
-fn write_header<U: OptFields, T: Write>(header: &Header<U>, stream: &mut T) {
-    write!(stream, "H").unwrap();
+fn write_header<U: OptFields, T: Write>(
+    header: &Header<U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "H")?;
     if let Some(v) = &header.version {
-        write!(stream, "\tVN:Z:{}", v.as_bstr()).unwrap();
+        write!(stream, "\tVN:Z:")?;
+        stream.write_all(v)?;
     }
-    write_optional_fields(&header.optional, stream);
+    write_optional_fields(&header.optional, stream)
 }
 
 // Write segment
 fn write_segment<N: SegmentId, T: Write, U: OptFields>(
     seg: &Segment<N, U>,
     stream: &mut T,
-) {
-    write!(
-        stream,
-        "S\t{}\t{}",
-        seg.name.display(),
-        seg.sequence.as_bstr()
-    )
-    .expect("Error writing segment to stream");
+) -> io::Result<()> {
+    write!(stream, "S\t")?;
+    seg.name.write_id(stream)?;
+    write!(stream, "\t")?;
+    stream.write_all(&seg.sequence)?;
 
-    write_optional_fields(&seg.optional, stream);
+    write_optional_fields(&seg.optional, stream)
 }
 
 // Write link
 fn write_link<N: SegmentId, T: Write, U: OptFields>(
     link: &Link<N, U>,
     stream: &mut T,
-) {
+) -> io::Result<()> {
+    write!(stream, "L\t")?;
+    link.from_segment.write_id(stream)?;
+    write!(stream, "\t{}\t", link.from_orient)?;
+    link.to_segment.write_id(stream)?;
+    write!(stream, "\t{}\t", link.to_orient)?;
+    stream.write_all(&link.overlap)?;
+
+    write_optional_fields(&link.optional, stream)
+}
+
+// Write jump
+fn write_jump<N: SegmentId, T: Write, U: OptFields>(
+    jump: &Jump<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "J\t")?;
+    jump.from_segment.write_id(stream)?;
+    write!(stream, "\t{}\t", jump.from_orient)?;
+    jump.to_segment.write_id(stream)?;
+    write!(stream, "\t{}\t", jump.to_orient)?;
+
+    match jump.distance {
+        Some(dist) => write!(stream, "{}", dist)?,
+        None => write!(stream, "*")?,
+    }
+
+    write_optional_fields(&jump.optional, stream)
+}
+
+// Write containment
+fn write_containment<N: SegmentId, T: Write, U: OptFields>(
+    containment: &Containment<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "C\t")?;
+    containment.container_name.write_id(stream)?;
+    write!(stream, "\t{}\t", containment.container_orient)?;
+    containment.contained_name.write_id(stream)?;
     write!(
         stream,
-        "L\t{}\t{}\t{}\t{}\t{}",
-        link.from_segment.display(),
-        link.from_orient,
-        link.to_segment.display(),
-        link.to_orient,
-        link.overlap.as_bstr(),
-    )
-    .expect("Error writing link to stream");
+        "\t{}\t{}\t",
+        containment.contained_orient, containment.pos
+    )?;
+    stream.write_all(&containment.overlap)?;
 
-    write_optional_fields(&link.optional, stream);
+    write_optional_fields(&containment.optional, stream)
 }
 
 // Write path
-fn write_path<N, U: OptFields, T: Write>(path: &Path<N, U>, stream: &mut T) {
-    write!(stream, "P\t{}\t", path.path_name.as_bstr())
-        .expect("Error writing path to stream");
+fn write_path<N, U: OptFields, T: Write>(
+    path: &Path<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "P\t")?;
+    stream.write_all(&path.path_name)?;
+    write!(stream, "\t")?;
+    stream.write_all(&path.segment_names)?;
+    write!(stream, "\t")?;
 
-    write!(stream, "{}\t", path.segment_names.as_bstr()).unwrap();
+    // Each overlap after the first is preceded by the separator of
+    // the step connection it describes, so that jumps are preserved
+    let mut separators = path.separators().skip(1);
 
-    path.overlaps.iter().enumerate().for_each(|(i, o)| {
+    for (i, o) in path.overlaps.iter().enumerate() {
         if i != 0 {
-            write!(stream, ",").unwrap();
+            let sep = separators.next().unwrap_or(b',');
+            write!(stream, "{}", char::from(sep))?;
         }
         match o {
-            None => write!(stream, "*").unwrap(),
-            // Some(o) => write!(stream, "{}", o.to_str().unwrap()).unwrap(),
-            Some(o) => write!(stream, "{}", o).unwrap(),
+            None => write!(stream, "*")?,
+            Some(o) => write!(stream, "{}", o)?,
+        }
+    }
+
+    write_optional_fields(&path.optional, stream)
+}
+
+// Write walk
+fn write_walk<N, U: OptFields, T: Write>(
+    walk: &Walk<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "W\t")?;
+    stream.write_all(&walk.sample_id)?;
+    write!(stream, "\t{}\t", walk.hap_index)?;
+    stream.write_all(&walk.seq_id)?;
+    write!(stream, "\t")?;
+
+    for coord in [walk.seq_start, walk.seq_end].iter() {
+        match coord {
+            Some(c) => write!(stream, "{}\t", c)?,
+            None => write!(stream, "*\t")?,
+        }
+    }
+
+    stream.write_all(&walk.walk)?;
+
+    write_optional_fields(&walk.optional, stream)
+}
+
+// Write GFA2 segment
+fn write_gfa2_segment<N: SegmentId, T: Write, U: OptFields>(
+    seg: &GFA2Segment<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "S\t")?;
+    seg.name.write_id(stream)?;
+    write!(stream, "\t{}\t", seg.length)?;
+    stream.write_all(&seg.sequence)?;
+
+    write_optional_fields(&seg.optional, stream)
+}
+
+fn write_optional_id<T: Write>(
+    id: &Option<Vec<u8>>,
+    stream: &mut T,
+) -> io::Result<()> {
+    match id {
+        Some(id) => stream.write_all(id),
+        None => write!(stream, "*"),
+    }
+}
+
+// Write edge
+fn write_edge<N: SegmentId, T: Write, U: OptFields>(
+    edge: &Edge<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "E\t")?;
+    write_optional_id(&edge.id, stream)?;
+    write!(stream, "\t")?;
+    edge.sid1.write_id(stream)?;
+    write!(stream, "{}\t", edge.sid1_orient)?;
+    edge.sid2.write_id(stream)?;
+    write!(
+        stream,
+        "{}\t{}\t{}\t{}\t{}\t",
+        edge.sid2_orient, edge.beg1, edge.end1, edge.beg2, edge.end2,
+    )?;
+    stream.write_all(&edge.alignment)?;
+
+    write_optional_fields(&edge.optional, stream)
+}
+
+// Write fragment
+fn write_fragment<N: SegmentId, T: Write, U: OptFields>(
+    fragment: &Fragment<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "F\t")?;
+    fragment.segment.write_id(stream)?;
+    write!(stream, "\t")?;
+    stream.write_all(&fragment.external)?;
+    write!(
+        stream,
+        "{}\t{}\t{}\t{}\t{}\t",
+        fragment.external_orient,
+        fragment.sbeg,
+        fragment.send,
+        fragment.fbeg,
+        fragment.fend,
+    )?;
+    stream.write_all(&fragment.alignment)?;
+
+    write_optional_fields(&fragment.optional, stream)
+}
+
+// Write gap
+fn write_gap<N: SegmentId, T: Write, U: OptFields>(
+    gap: &Gap<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "G\t")?;
+    write_optional_id(&gap.id, stream)?;
+    write!(stream, "\t")?;
+    gap.sid1.write_id(stream)?;
+    write!(stream, "{}\t", gap.sid1_orient)?;
+    gap.sid2.write_id(stream)?;
+    write!(stream, "{}\t{}\t", gap.sid2_orient, gap.distance)?;
+
+    match gap.variance {
+        Some(var) => write!(stream, "{}", var)?,
+        None => write!(stream, "*")?,
+    }
+
+    write_optional_fields(&gap.optional, stream)
+}
+
+// Write ordered group
+fn write_ordered_group<N, U: OptFields, T: Write>(
+    group: &OrderedGroup<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "O\t")?;
+    write_optional_id(&group.id, stream)?;
+    write!(stream, "\t")?;
+    stream.write_all(&group.items)?;
+
+    write_optional_fields(&group.optional, stream)
+}
+
+// Write unordered group
+fn write_unordered_group<N, U: OptFields, T: Write>(
+    group: &UnorderedGroup<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write!(stream, "U\t")?;
+    write_optional_id(&group.id, stream)?;
+    write!(stream, "\t")?;
+    stream.write_all(&group.items)?;
+
+    write_optional_fields(&group.optional, stream)
+}
+
+// Write a line that was kept as-is
+fn write_raw_line<T: Write>(line: &[u8], stream: &mut T) -> io::Result<()> {
+    stream.write_all(line)
+}
+
+// Write any line, without a newline
+fn write_line_ref<N: SegmentId, T: Write, U: OptFields>(
+    line: &LineRef<'_, N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    match *line {
+        LineRef::Header(h) => write_header(h, stream),
+        LineRef::Segment(s) => write_segment(s, stream),
+        LineRef::Link(l) => write_link(l, stream),
+        LineRef::Jump(j) => write_jump(j, stream),
+        LineRef::Containment(c) => write_containment(c, stream),
+        LineRef::Path(p) => write_path(p, stream),
+        LineRef::Walk(w) => write_walk(w, stream),
+        LineRef::GFA2Segment(s) => write_gfa2_segment(s, stream),
+        LineRef::Edge(e) => write_edge(e, stream),
+        LineRef::Fragment(f) => write_fragment(f, stream),
+        LineRef::Gap(g) => write_gap(g, stream),
+        LineRef::OrderedGroup(o) => write_ordered_group(o, stream),
+        LineRef::UnorderedGroup(u) => write_unordered_group(u, stream),
+        LineRef::Raw(r) => write_raw_line(r, stream),
+    }
+}
+
+/// Write a line, followed by a newline.
+pub fn write_line<N: SegmentId, T: Write, U: OptFields>(
+    line: LineRef<'_, N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    write_line_ref(&line, stream)?;
+    writeln!(stream)
+}
+
+// Whether the header has anything to write, as a header line needs
+// at least one tag
+fn is_empty_header<U: OptFields>(header: &Header<U>) -> bool {
+    header.version.is_none() && header.optional.fields().is_empty()
+}
+
+// Write the lines of a GFA without buffering. If the GFA keeps track
+// of the line order, the lines are written in that order, including
+// any comments and other raw lines, so that a file parsed with
+// `preserve_lines` is written as it was.
+fn write_gfa_lines<N: SegmentId, T: Write, U: OptFields>(
+    gfa: &GFA<N, U>,
+    stream: &mut T,
+) -> io::Result<()> {
+    // A GFA without header lines has an empty header, which isn't
+    // written. A header that's not in the recorded order, e.g. one
+    // that was set after parsing a file without one, comes first
+    let header = &gfa.header;
+    let recorded = gfa
+        .line_order
+        .as_ref()
+        .is_some_and(|order| order.contains(&(LineKind::Header, 0)));
+    if !recorded && !is_empty_header(header) {
+        write_line::<N, _, _>(LineRef::Header(header), stream)?;
+    }
+    for line in gfa.lines_iter() {
+        if let LineRef::Header(header) = line {
+            if is_empty_header(header) {
+                continue;
+            }
+        }
+        write_line(line, stream)?;
+    }
+    if gfa.line_order.is_none() {
+        for line in gfa.raw_lines.iter() {
+            write_line::<N, _, U>(LineRef::Raw(line), stream)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a GFA to a `std::io::Write`, such as a file. The output is
+/// buffered, and flushed once the GFA has been written.
+pub fn write_gfa_io<N: SegmentId, T: Write, U: OptFields>(
+    gfa: &GFA<N, U>,
+    stream: T,
+) -> io::Result<()> {
+    let mut stream = BufWriter::new(stream);
+    write_gfa_lines(gfa, &mut stream)?;
+    stream.flush()
+}
+
+/// Writes a GFA one line at a time, for GFAs that are too large to
+/// keep in memory. The header is written when the writer is created,
+/// and the output is buffered.
+///
+/// By default, lines are written in the order they're given. A writer
+/// created with `with_conventional_order` instead writes the header,
+/// then the segments, then the links, jumps and containments, and
+/// then the paths and walks, by writing lines that come after the
+/// segments to temporary files until the writer is finished. The
+/// GFA2 lines are ordered the same way, with edges, fragments and
+/// gaps before the groups. Comments and other raw lines are written
+/// along with the segments.
+///
+/// The remaining lines are written, and the output flushed, when the
+/// writer is finished or dropped. Errors are ignored when the writer
+/// is dropped, so use `finish` to handle them.
+pub struct GFAWriter<W: Write> {
+    // Only taken by `finish`
+    stream: Option<BufWriter<W>>,
+    conventional_order: bool,
+    // The temporary files of the lines written after the segments,
+    // created when the first such line is written
+    deferred: [Option<BufWriter<File>>; 2],
+}
+
+impl<W: Write> GFAWriter<W> {
+    /// Create a writer that writes the lines in the order they're
+    /// given, after writing the header.
+    pub fn new<U: OptFields>(
+        stream: W,
+        header: &Header<U>,
+    ) -> io::Result<Self> {
+        let mut writer = GFAWriter {
+            stream: Some(BufWriter::new(stream)),
+            conventional_order: false,
+            deferred: [None, None],
+        };
+        if !is_empty_header(header) {
+            let stream = writer.stream.as_mut().unwrap();
+            write_line::<usize, _, _>(LineRef::Header(header), stream)?;
         }
-    });
+        Ok(writer)
+    }
+
+    /// Create a writer that writes the lines in the H/S/L/P order,
+    /// after writing the header.
+    pub fn with_conventional_order<U: OptFields>(
+        stream: W,
+        header: &Header<U>,
+    ) -> io::Result<Self> {
+        let mut writer = Self::new(stream, header)?;
+        writer.conventional_order = true;
+        Ok(writer)
+    }
+
+    /// Write a line. As the header has already been written, header
+    /// lines can't be written, and return an `InvalidInput` error.
+    pub fn write_line<N: SegmentId, U: OptFields>(
+        &mut self,
+        line: &Line<N, U>,
+    ) -> io::Result<()> {
+        self.write_line_ref(line.as_line_ref())
+    }
+
+    /// Write a borrowed line, like `write_line`.
+    pub fn write_line_ref<N: SegmentId, U: OptFields>(
+        &mut self,
+        line: LineRef<'_, N, U>,
+    ) -> io::Result<()> {
+        use LineRef::*;
+
+        let deferred = match line {
+            Header(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the header has already been written",
+                ))
+            }
+            _ if !self.conventional_order => None,
+            Segment(_) | GFA2Segment(_) | Raw(_) => None,
+            Link(_) | Jump(_) | Containment(_) | Edge(_) | Fragment(_)
+            | Gap(_) => Some(0),
+            Path(_) | Walk(_) | OrderedGroup(_) | UnorderedGroup(_) => Some(1),
+        };
+
+        match deferred {
+            None => write_line(line, self.stream.as_mut().unwrap()),
+            Some(ix) => {
+                let file = match &mut self.deferred[ix] {
+                    Some(file) => file,
+                    file => file.insert(BufWriter::new(tempfile::tempfile()?)),
+                };
+                write_line(line, file)
+            }
+        }
+    }
+
+    /// Flush the lines that have been written to the output, except
+    /// for lines that are kept back to write them in the
+    /// conventional order.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.stream.as_mut().unwrap().flush()
+    }
+
+    /// Write the lines that were kept back, flush the output, and
+    /// return it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_deferred()?;
+        let stream = self.stream.take().unwrap();
+        stream.into_inner().map_err(|err| err.into_error())
+    }
+
+    // Append the temporary files to the output, in order, and flush it
+    fn write_deferred(&mut self) -> io::Result<()> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => return Ok(()),
+        };
+        for file in self.deferred.iter_mut() {
+            if let Some(file) = file.take() {
+                let mut file = file.into_inner().map_err(|e| e.into_error())?;
+                file.seek(SeekFrom::Start(0))?;
+                io::copy(&mut file, stream)?;
+            }
+        }
+        stream.flush()
+    }
+}
+
+impl<W: Write> Drop for GFAWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.write_deferred();
+    }
+}
+
+/// Adapts a `std::fmt::Write` to a `std::io::Write`, so that the same
+/// functions can write to both. Bytes that aren't valid UTF-8 are
+/// replaced, as when displaying them with `as_bstr()`.
+struct FmtWriter<'a, T>(&'a mut T);
+
+impl<'a, T: fmt::Write> Write for FmtWriter<'a, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .write_str(&buf.to_str_lossy())
+            .map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
 
-    write_optional_fields(&path.optional, stream);
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
-// Write GFA
-pub fn write_gfa<N: SegmentId, T: Write, U: OptFields>(
+/// Write a GFA to a `std::fmt::Write`, such as a `String`. Panics if
+/// the stream returns an error; use `write_gfa_io` to handle errors.
+pub fn write_gfa<N: SegmentId, T: fmt::Write, U: OptFields>(
     gfa: &GFA<N, U>,
     stream: &mut T,
 ) {
-    write_header(&gfa.header, stream);
-    writeln!(stream).unwrap();
+    write_gfa_lines(gfa, &mut FmtWriter(stream))
+        .expect("Error writing GFA to stream")
+}
+
+// The Display implementations write the lines without a newline, as
+// they're written in a GFA file.
+macro_rules! impl_display {
+    ($ty:ident, $write:ident) => {
+        impl<N: SegmentId, U: OptFields> fmt::Display for $ty<N, U> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                $write(self, &mut FmtWriter(f)).map_err(|_| fmt::Error)
+            }
+        }
+    };
+}
 
-    gfa.segments.iter().for_each(|s| {
-        write_segment(s, stream);
-        writeln!(stream).unwrap();
-    });
+impl_display!(Segment, write_segment);
+impl_display!(Link, write_link);
+impl_display!(Jump, write_jump);
+impl_display!(Containment, write_containment);
+impl_display!(Path, write_path);
+impl_display!(Walk, write_walk);
+impl_display!(GFA2Segment, write_gfa2_segment);
+impl_display!(Edge, write_edge);
+impl_display!(Fragment, write_fragment);
+impl_display!(Gap, write_gap);
+impl_display!(OrderedGroup, write_ordered_group);
+impl_display!(UnorderedGroup, write_unordered_group);
 
-    gfa.links.iter().for_each(|l| {
-        write_link(l, stream);
-        writeln!(stream).unwrap();
-    });
+impl<U: OptFields> fmt::Display for Header<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_header(self, &mut FmtWriter(f)).map_err(|_| fmt::Error)
+    }
+}
 
-    gfa.paths.iter().for_each(|p| {
-        write_path(p, stream);
-        writeln!(stream).unwrap();
-    });
+impl<N: SegmentId, U: OptFields> fmt::Display for Line<N, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_line_ref().fmt(f)
+    }
+}
+
+impl<'a, N: SegmentId, U: OptFields> fmt::Display for LineRef<'a, N, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_line_ref(self, &mut FmtWriter(f)).map_err(|_| fmt::Error)
+    }
+}
+
+/// Displays the GFA as it's written by `write_gfa`, with each line
+/// followed by a newline.
+impl<N: SegmentId, U: OptFields> fmt::Display for GFA<N, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_gfa_lines(self, &mut FmtWriter(f)).map_err(|_| fmt::Error)
+    }
 }
 
 pub fn gfa_string(gfa: &GFA<Vec<u8>, OptionalFields>) -> String {
@@ -117,20 +588,32 @@ mod tests {
     use super::*;
     use crate::gfa::Orientation;
 
+    // Write a single line without the newline to a string
+    fn line_string<F>(write: F) -> String
+    where
+        F: FnOnce(&mut Vec<u8>) -> io::Result<()>,
+    {
+        let mut bytes = Vec::new();
+        write(&mut bytes).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
     #[test]
     fn print_segment() {
         use OptFieldVal::*;
         let mut segment: Segment<Vec<u8>, OptionalFields> =
             Segment::new(b"seg1", b"GCCCTA");
         let opt_ij = OptField::new(b"IJ", A(b'x'));
-        let opt_ab = OptField::new(b"AB", BInt(vec![1, 2, 3, 52124]));
+        let opt_ab = OptField::new(
+            b"AB",
+            BInt(crate::optfields::IntArrayType::U32, vec![1, 2, 3, 52124]),
+        );
         let opt_ur =
             OptField::new(b"UR", Z(Vec::<u8>::from("http://test.com/")));
         let opt_rc = OptField::new(b"RC", Int(123));
         segment.optional = vec![opt_rc, opt_ur, opt_ij, opt_ab];
-        let expected = "S\tseg1\tGCCCTA\tRC:i:123\tUR:Z:http://test.com/\tIJ:A:x\tAB:B:I1,2,3,52124";
-        let mut string = String::new();
-        write_segment(&segment, &mut string);
+        let expected = "S\tseg1\tGCCCTA\tRC:i:123\tUR:Z:http://test.com/\tIJ:A:x\tAB:B:I,1,2,3,52124";
+        let string = line_string(|out| write_segment(&segment, out));
         assert_eq!(string, expected);
     }
 
@@ -143,8 +626,7 @@ mod tests {
             Orientation::Backward,
             b"0M",
         );
-        let mut string = String::new();
-        write_link(&link, &mut string);
+        let string = line_string(|out| write_link(&link, out));
         assert_eq!(string, "L\t13\t+\t552\t-\t0M");
     }
 
@@ -152,7 +634,7 @@ mod tests {
     fn print_path() {
         use crate::cigar::CIGAR;
 
-        let cigars = vec![b"8M", b"1M", b"3M"]
+        let cigars = [b"8M", b"1M", b"3M"]
             .iter()
             .map(|bs| CIGAR::from_bytestring(&bs[..]))
             .collect();
@@ -160,11 +642,136 @@ mod tests {
         let path: Path<Vec<u8>, _> =
             Path::new("path1".into(), "13+,51-,241+".into(), cigars, ());
 
-        let mut string = String::new();
-        write_path(&path, &mut string);
+        let string = line_string(|out| write_path(&path, out));
         assert_eq!(string, "P\tpath1\t13+,51-,241+\t8M,1M,3M");
     }
 
+    #[test]
+    fn print_jump() {
+        let jump: Jump<Vec<u8>, ()> = Jump::new(
+            b"13",
+            Orientation::Forward,
+            b"552",
+            Orientation::Backward,
+            None,
+        );
+        let string = line_string(|out| write_jump(&jump, out));
+        assert_eq!(string, "J\t13\t+\t552\t-\t*");
+    }
+
+    #[test]
+    fn print_path_with_jumps() {
+        use crate::cigar::CIGAR;
+
+        let cigars = vec![CIGAR::from_bytestring(b"8M"), None];
+
+        let path: Path<Vec<u8>, _> =
+            Path::new("path1".into(), "13+,51-;241+".into(), cigars, ());
+
+        let string = line_string(|out| write_path(&path, out));
+        assert_eq!(string, "P\tpath1\t13+,51-;241+\t8M;*");
+    }
+
+    #[test]
+    fn print_walk() {
+        let walk: Walk<Vec<u8>, _> = Walk::new(
+            "HG002".into(),
+            2,
+            "chr1".into(),
+            None,
+            Some(1200),
+            ">13<51>241".into(),
+            (),
+        );
+
+        let string = line_string(|out| write_walk(&walk, out));
+        assert_eq!(string, "W\tHG002\t2\tchr1\t*\t1200\t>13<51>241");
+    }
+
+    #[test]
+    fn print_edge() {
+        let edge: Edge<Vec<u8>, ()> = Edge {
+            id: None,
+            sid1: "5".into(),
+            sid1_orient: Orientation::Forward,
+            sid2: "6".into(),
+            sid2_orient: Orientation::Backward,
+            beg1: Position::new(0, false),
+            end1: Position::new(10, true),
+            beg2: Position::new(0, false),
+            end2: Position::new(10, false),
+            alignment: "10M".into(),
+            optional: (),
+        };
+        let string = line_string(|out| write_edge(&edge, out));
+        assert_eq!(string, "E\t*\t5+\t6-\t0\t10$\t0\t10\t10M");
+    }
+
+    #[test]
+    fn print_containment() {
+        let containment: Containment<Vec<u8>, OptionalFields> = Containment {
+            container_name: "1".into(),
+            container_orient: Orientation::Forward,
+            contained_name: "3".into(),
+            contained_orient: Orientation::Backward,
+            pos: 2,
+            overlap: "4M".into(),
+            optional: vec![OptField::new(b"RC", OptFieldVal::Int(3))],
+        };
+        let string = line_string(|out| write_containment(&containment, out));
+        assert_eq!(string, "C\t1\t+\t3\t-\t2\t4M\tRC:i:3");
+    }
+
+    // These GFAs are written exactly as they're found in the files,
+    // as their lines are in the order they're written in
+    #[test]
+    fn print_test_gfas() {
+        let parser = crate::parser::GFAParser::new();
+        for name in ["containments", "gfa2", "jumps", "walks"].iter() {
+            let path = format!("./test/gfas/{}.gfa", name);
+            let in_gfa: GFA<Vec<u8>, OptionalFields> =
+                parser.parse_file(&path).unwrap();
+            let file_string = std::fs::read_to_string(&path).unwrap();
+
+            let mut string = String::new();
+            write_gfa(&in_gfa, &mut string);
+            assert_eq!(string, file_string, "{}", path);
+        }
+    }
+
+    // Every GFA under test/gfas/, including the compressed ones, is
+    // the same after being written and parsed again, both with and
+    // without preserving the line order
+    #[test]
+    fn round_trip_test_gfas() {
+        use crate::parser::GFAParserBuilder;
+
+        let mut paths: Vec<_> = std::fs::read_dir("./test/gfas")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                let name = path.file_name().unwrap().to_string_lossy();
+                name.ends_with(".gfa") || name.ends_with(".gfa.gz")
+            })
+            .collect();
+        paths.sort();
+        assert!(paths.len() > 10);
+
+        for preserve in [false, true].iter().copied() {
+            let parser: crate::parser::GFAParser<Vec<u8>, OptionalFields> =
+                GFAParserBuilder::all().preserve_lines(preserve).build();
+
+            for path in paths.iter() {
+                let gfa = parser.parse_file(path).unwrap();
+                let mut bytes = Vec::new();
+                write_gfa_io(&gfa, &mut bytes).unwrap();
+                let reparsed =
+                    parser.parse_lines(ByteSlice::lines(&bytes[..])).unwrap();
+                assert_eq!(gfa, reparsed, "{}", path.display());
+            }
+        }
+    }
+
     #[test]
     fn print_gfa() {
         use std::io::Read;
@@ -172,10 +779,10 @@ mod tests {
 
         let parser = crate::parser::GFAParser::new();
         let in_gfa: GFA<Vec<u8>, ()> =
-            parser.parse_file(&"./test/gfas/lil.gfa").unwrap();
+            parser.parse_file("./test/gfas/lil.gfa").unwrap();
 
         let mut file =
-            std::fs::File::open(&PathBuf::from("./test/gfas/lil.gfa")).unwrap();
+            std::fs::File::open(PathBuf::from("./test/gfas/lil.gfa")).unwrap();
         let mut file_string = String::new();
         file.read_to_string(&mut file_string).unwrap();
 
@@ -184,4 +791,199 @@ mod tests {
 
         assert_eq!(string, file_string);
     }
+
+    #[test]
+    fn print_gfa_preserved_lines() {
+        use crate::parser::GFAParserBuilder;
+
+        let parser = GFAParserBuilder::all().preserve_lines(true).build();
+        let mut in_gfa: GFA<Vec<u8>, OptionalFields> =
+            parser.parse_file("./test/gfas/comments.gfa").unwrap();
+
+        let file_string =
+            std::fs::read_to_string("./test/gfas/comments.gfa").unwrap();
+
+        let mut string = String::new();
+        write_gfa(&in_gfa, &mut string);
+        assert_eq!(string, file_string);
+
+        // Only the edited line changes
+        in_gfa.segments[1].sequence = b"C".to_vec();
+        let mut string = String::new();
+        write_gfa(&in_gfa, &mut string);
+        assert_eq!(string, file_string.replace("S\t2\tA\t", "S\t2\tC\t"));
+    }
+
+    #[test]
+    fn write_gfa_to_io() {
+        let parser = crate::parser::GFAParser::new();
+        let gfa: GFA<Vec<u8>, OptionalFields> =
+            parser.parse_file("./test/gfas/lil.gfa").unwrap();
+
+        let mut bytes = Vec::new();
+        write_gfa_io(&gfa, &mut bytes).unwrap();
+        assert_eq!(bytes, std::fs::read("./test/gfas/lil.gfa").unwrap());
+
+        // Errors are returned rather than panicking
+        struct Full;
+        impl Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("full"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = write_gfa_io(&gfa, Full).unwrap_err();
+        assert_eq!(err.to_string(), "full");
+    }
+
+    #[test]
+    fn write_bytes_unchanged() {
+        let parser = crate::parser::GFAParserBuilder::all()
+            .preserve_lines(true)
+            .build();
+        let input: &[u8] = b"H\tVN:Z:1.0\n# caf\xe9\nS\t1\tACGT\nP\tp\t1+\t*\n";
+        let mut gfa: GFA<usize, OptionalFields> =
+            parser.parse_lines(ByteSlice::lines(input)).unwrap();
+
+        let mut bytes = Vec::new();
+        write_gfa_io(&gfa, &mut bytes).unwrap();
+        assert_eq!(bytes, input);
+
+        gfa.segments[0].optional =
+            vec![OptField::new(b"xx", OptFieldVal::Z(b"caf\xe9".to_vec()))];
+        gfa.paths[0].path_name = b"p\xe9".to_vec();
+        let mut bytes = Vec::new();
+        write_gfa_io(&gfa, &mut bytes).unwrap();
+        assert_eq!(
+            bytes,
+            &b"H\tVN:Z:1.0\n# caf\xe9\n\
+                S\t1\tACGT\txx:Z:caf\xe9\nP\tp\xe9\t1+\t*\n"[..]
+        );
+
+        // Only writing to a `fmt::Write` replaces them
+        let string = gfa.to_string();
+        assert!(string.contains("# caf\u{FFFD}\n"));
+    }
+
+    #[test]
+    fn write_header_with_line_order() {
+        let input: &[u8] = b"S\t1\tACGT\n";
+        let parser = crate::parser::GFAParser::new();
+        let mut gfa: GFA<usize, OptionalFields> =
+            parser.parse_lines(ByteSlice::lines(input)).unwrap();
+
+        // An empty header isn't written, even though it's in the order
+        gfa.track_line_order();
+        assert_eq!(gfa.line_order.as_ref().unwrap()[0], (LineKind::Header, 0));
+        let mut bytes = Vec::new();
+        write_gfa_io(&gfa, &mut bytes).unwrap();
+        assert_eq!(bytes, input);
+
+        let parser = crate::parser::GFAParserBuilder::all()
+            .preserve_lines(true)
+            .build();
+        let input: &[u8] = b"# no header\nS\t1\tACGT\n";
+        let mut gfa: GFA<usize, OptionalFields> =
+            parser.parse_lines(ByteSlice::lines(input)).unwrap();
+        let mut bytes = Vec::new();
+        write_gfa_io(&gfa, &mut bytes).unwrap();
+        assert_eq!(bytes, input);
+
+        // A header that wasn't parsed is written before the lines
+        gfa.header.version = Some(b"1.0".to_vec());
+        let mut bytes = Vec::new();
+        write_gfa_io(&gfa, &mut bytes).unwrap();
+        assert_eq!(bytes, b"H\tVN:Z:1.0\n# no header\nS\t1\tACGT\n");
+    }
+
+    #[test]
+    fn display_lines() {
+        let parser = crate::parser::GFAParserBuilder::all()
+            .preserve_lines(true)
+            .build();
+        let gfa: GFA<usize, OptionalFields> =
+            parser.parse_file("./test/gfas/comments.gfa").unwrap();
+        let file_string =
+            std::fs::read_to_string("./test/gfas/comments.gfa").unwrap();
+        assert_eq!(gfa.to_string(), file_string);
+
+        // Each line is displayed as it's written in the file
+        let lines: Vec<_> = gfa.lines_iter().map(|l| l.to_string()).collect();
+        assert_eq!(lines, file_string.lines().collect::<Vec<_>>());
+
+        assert_eq!(gfa.header.to_string(), "H\tVN:Z:1.0");
+        assert_eq!(gfa.segments[1].to_string(), "S\t2\tA\tLN:i:1");
+        assert_eq!(format!("{}", gfa.links[0]), "L\t1\t+\t2\t+\t0M");
+
+        let line: Line<usize, OptionalFields> =
+            parser.parse_gfa_line(b"C\t1\t+\t3\t-\t2\t4M").unwrap();
+        assert_eq!(line.to_string(), "C\t1\t+\t3\t-\t2\t4M");
+        let containment = line.some_containment().map(|c| c.to_string());
+        assert_eq!(containment.as_deref(), Some("C\t1\t+\t3\t-\t2\t4M"));
+        let raw: Line<usize, ()> = Line::Raw(b"# comment".to_vec());
+        assert_eq!(raw.to_string(), "# comment");
+    }
+
+    #[test]
+    fn streaming_writer() {
+        let parser = crate::parser::GFAParser::new();
+        let input = std::fs::read("./test/gfas/containments.gfa").unwrap();
+        let mut lines: Vec<Line<usize, OptionalFields>> =
+            ByteSlice::lines(&input[..])
+                .map(|line| parser.parse_gfa_line(line).unwrap())
+                .collect();
+        let header = lines.remove(0).some_header().unwrap();
+
+        // In the given order
+        let mut writer = GFAWriter::new(Vec::new(), &header).unwrap();
+        for line in lines.iter().rev() {
+            writer.write_line(line).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+        let mut expected: Vec<_> = ByteSlice::lines(&input[..]).collect();
+        expected[1..].reverse();
+        assert_eq!(ByteSlice::lines(&bytes[..]).collect::<Vec<_>>(), expected);
+
+        // In the conventional order, which containments.gfa is in
+        let mut writer =
+            GFAWriter::with_conventional_order(Vec::new(), &header).unwrap();
+        for line in lines.iter().rev() {
+            writer.write_line(line).unwrap();
+        }
+        let header_line: Line<usize, _> = Line::Header(header.clone());
+        let err = writer.write_line(&header_line).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let bytes = writer.finish().unwrap();
+        // Lines of the same kind are still reversed
+        let mut expected: Vec<_> = ByteSlice::lines(&input[..]).collect();
+        expected[1..4].reverse();
+        expected[4..7].reverse();
+        assert_eq!(ByteSlice::lines(&bytes[..]).collect::<Vec<_>>(), expected);
+
+        // Dropping the writer flushes it
+        let mut bytes = Vec::new();
+        {
+            let mut writer =
+                GFAWriter::with_conventional_order(&mut bytes, &header)
+                    .unwrap();
+            for line in lines.iter() {
+                writer.write_line_ref(line.as_line_ref()).unwrap();
+            }
+        }
+        assert_eq!(bytes, input);
+    }
+
+    #[test]
+    fn print_merged_header() {
+        let parser = crate::parser::GFAParser::new();
+        let input: &[u8] = b"H\tVN:Z:1.0\nS\t1\tA\nH\tff:Z:tool\n";
+        let gfa: GFA<Vec<u8>, OptionalFields> =
+            parser.parse_lines(ByteSlice::lines(input)).unwrap();
+
+        let mut string = String::new();
+        write_gfa(&gfa, &mut string);
+        assert_eq!(string, "H\tVN:Z:1.0\tff:Z:tool\nS\t1\tA\n");
+    }
 }
@@ -0,0 +1,103 @@
+#![cfg(feature = "derive")]
+
+use gfa::{
+    gfa::{Line, GFA},
+    optfields::{OptField, OptFieldVal, OptFields},
+    parser::{GFAParser, GFAParserBuilder},
+    writer::write_gfa,
+};
+
+#[derive(Debug, Default, Clone, PartialEq, OptFields)]
+struct SegmentTags {
+    #[tag("LN")]
+    length: Option<i64>,
+    #[tag("RC")]
+    read_count: Option<i64>,
+    #[other_tags]
+    rest: Vec<OptField>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, OptFields)]
+struct LengthOnly {
+    #[tag("LN")]
+    length: Option<i64>,
+}
+
+const INPUT: &str = "S\t1\tACGT\tLN:i:4\txx:Z:abc\tRC:i:10\nS\t2\tAC\tLN:Z:two";
+
+#[test]
+fn derived_fields_are_typed() {
+    let parser: GFAParser<usize, SegmentTags> = GFAParser::new();
+    let gfa: GFA<usize, SegmentTags> = parser
+        .parse_lines(INPUT.lines().map(str::as_bytes))
+        .unwrap();
+
+    let tags = &gfa.segments[0].optional;
+    assert_eq!(tags.length, Some(4));
+    assert_eq!(tags.read_count, Some(10));
+    assert_eq!(
+        tags.rest,
+        vec![OptField::new(b"xx", OptFieldVal::Z(b"abc".to_vec()))]
+    );
+    assert_eq!(
+        tags.get_field(b"RC").map(|f| f.value.clone()),
+        Some(OptFieldVal::Int(10))
+    );
+    assert!(tags.get_field(b"xx").is_some());
+    assert!(tags.get_field(b"yy").is_none());
+    // The field with the wrong type is skipped with a warning
+    assert_eq!(gfa.segments[1].optional, SegmentTags::default());
+
+    let mut written = String::new();
+    write_gfa(&gfa, &mut written);
+    assert!(written.contains("S\t1\tACGT\tLN:i:4\tRC:i:10\txx:Z:abc"));
+}
+
+#[test]
+fn derived_fields_without_catch_all() {
+    let parser: GFAParser<usize, LengthOnly> = GFAParser::new();
+    let gfa: GFA<usize, LengthOnly> = parser
+        .parse_lines(INPUT.lines().map(str::as_bytes))
+        .unwrap();
+    assert_eq!(gfa.segments[0].optional.length, Some(4));
+    assert_eq!(gfa.segments[0].optional.fields().len(), 1);
+}
+
+#[test]
+fn derived_fields_type_mismatch_is_an_error() {
+    let parser: GFAParser<usize, SegmentTags> =
+        GFAParserBuilder::all().pedantic_errors().build();
+    let line = parser.parse_gfa_line(b"S\t2\tAC\tLN:Z:two");
+    assert!(line.is_err());
+    let line = parser.parse_gfa_line(b"S\t2\tAC\tLN:i:2");
+    assert!(
+        matches!(line, Ok(Line::Segment(s)) if s.optional.length == Some(2))
+    );
+}
+
+#[test]
+fn derived_fields_merge() {
+    let parse = |fields: &[&str]| {
+        SegmentTags::parse(fields.iter().map(|f| f.as_bytes())).unwrap()
+    };
+    let mut tags = parse(&["LN:i:4", "xx:Z:abc", "yy:i:1"]);
+    tags.merge(parse(&["RC:i:10", "xx:Z:def"]));
+    assert_eq!(tags.length, Some(4));
+    assert_eq!(tags.read_count, Some(10));
+    assert_eq!(
+        tags.rest,
+        vec![
+            OptField::new(b"yy", OptFieldVal::Int(1)),
+            OptField::new(b"xx", OptFieldVal::Z(b"def".to_vec())),
+        ]
+    );
+
+    // Header lines are merged into the header of the GFA
+    let input = "H\tVN:Z:1.0\tLN:i:1\nH\tRC:i:2\tLN:i:3";
+    let parser: GFAParser<usize, SegmentTags> = GFAParser::new();
+    let gfa = parser
+        .parse_lines(input.lines().map(str::as_bytes))
+        .unwrap();
+    assert_eq!(gfa.header.optional.length, Some(3));
+    assert_eq!(gfa.header.optional.read_count, Some(2));
+}